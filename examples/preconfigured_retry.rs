@@ -1,11 +1,9 @@
 use anyhow::anyhow;
 use reqwest::{RequestBuilder, Response};
 use retry_future::{
-    ExponentialRetryStrategy, LinearRetryStrategy, RetryError, RetryFuture, RetryPolicy,
+    BoxRetryFuture, ExponentialRetryStrategy, LinearRetryStrategy, RetryFuture, RetryPolicy,
     RetryStrategy,
 };
-use std::future::Future;
-use std::pin::Pin;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -34,7 +32,7 @@ trait WithRetryStrategy {
     fn with_retry_strategy<RS: RetryStrategy + 'static>(
         self,
         retry_strategy: RS,
-    ) -> Pin<Box<dyn Future<Output = Result<Self::Ok, RetryError<Self::Err>>>>>;
+    ) -> BoxRetryFuture<Self::Ok, Self::Err>;
 }
 
 impl WithRetryStrategy for RequestBuilder {
@@ -44,12 +42,12 @@ impl WithRetryStrategy for RequestBuilder {
     fn with_retry_strategy<RS: RetryStrategy + 'static>(
         self,
         retry_strategy: RS,
-    ) -> Pin<Box<dyn Future<Output = Result<Self::Ok, RetryError<Self::Err>>>>> {
-        Box::pin(async move {
-            RetryFuture::new(
-                || async {
-                    let resp =
-                        self.try_clone().ok_or(anyhow!("RequestBody is a stream!"))?.send().await?;
+    ) -> BoxRetryFuture<Self::Ok, Self::Err> {
+        RetryFuture::new(
+            move || {
+                let request = self.try_clone();
+                async move {
+                    let resp = request.ok_or(anyhow!("RequestBody is a stream!"))?.send().await?;
                     if resp.status().is_success() {
                         Ok(resp)
                     } else if resp.status().is_server_error() {
@@ -57,10 +55,10 @@ impl WithRetryStrategy for RequestBuilder {
                     } else {
                         Err(RetryPolicy::Fail(resp))
                     }
-                },
-                retry_strategy,
-            )
-            .await
-        })
+                }
+            },
+            retry_strategy,
+        )
+        .boxed()
     }
 }
@@ -0,0 +1,40 @@
+use retry_future::{ExponentialRetryStrategy, RetryFuture, RetryPolicy};
+use sqlx::PgPool;
+use std::time::Duration;
+
+/// Moves `amount` between accounts in a serializable transaction which
+/// may be aborted by the database if it conflicts with a concurrent one
+async fn transfer(pool: &PgPool, from: i64, to: i64, amount: i64) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE").execute(&mut *tx).await?;
+    sqlx::query("UPDATE accounts SET balance = balance - $1 WHERE id = $2")
+        .bind(amount)
+        .bind(from)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE accounts SET balance = balance + $1 WHERE id = $2")
+        .bind(amount)
+        .bind(to)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let pool = PgPool::connect("postgres://localhost/bank").await?;
+
+    // Serialization failures and deadlocks are retried, the whole transaction
+    // is started from scratch. Constraint violations fail immediately
+    RetryFuture::new(
+        || async {
+            transfer(&pool, 1, 2, 100).await.map_err(|e| RetryPolicy::<String>::from_sqlx_error(&e))
+        },
+        ExponentialRetryStrategy::default()
+            .max_attempts(5)
+            .initial_delay(Duration::from_millis(10)),
+    )
+    .await?;
+
+    Ok(())
+}
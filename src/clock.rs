@@ -0,0 +1,121 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Source of time for [RetryFuture](crate::RetryFuture).
+///
+/// By default [TokioClock] is used, but you can plug in your own implementation,
+/// e.g. a virtual clock in simulations or tests.
+pub trait Clock {
+    type Sleep: Future<Output = ()>;
+
+    /// Current point in time
+    fn now(&self) -> Instant;
+
+    /// Future which resolves after `duration` has passed
+    fn sleep(&self, duration: Duration) -> Self::Sleep;
+}
+
+/// [Clock] backed by the tokio timer, so it respects
+/// paused time (`tokio::time::pause`) as well
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    type Sleep = tokio::time::Sleep;
+
+    fn now(&self) -> Instant {
+        tokio::time::Instant::now().into_std()
+    }
+
+    fn sleep(&self, duration: Duration) -> Self::Sleep {
+        tokio::time::sleep(duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LinearRetryStrategy, RetryFuture, RetryPolicy};
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::sync::watch;
+
+    /// Clock which moves forward only when [VirtualClock::advance] is called
+    #[derive(Clone)]
+    struct VirtualClock {
+        start: Instant,
+        elapsed: Arc<watch::Sender<Duration>>,
+    }
+
+    impl VirtualClock {
+        fn new() -> Self {
+            Self { start: Instant::now(), elapsed: Arc::new(watch::channel(Duration::ZERO).0) }
+        }
+
+        fn advance(&self, duration: Duration) {
+            self.elapsed.send_modify(|elapsed| *elapsed += duration);
+        }
+    }
+
+    impl Clock for VirtualClock {
+        type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+        fn now(&self) -> Instant {
+            self.start + *self.elapsed.borrow()
+        }
+
+        fn sleep(&self, duration: Duration) -> Self::Sleep {
+            let mut elapsed = self.elapsed.subscribe();
+            let wake_at = *elapsed.borrow() + duration;
+            Box::pin(async move {
+                let _ = elapsed.wait_for(|elapsed| *elapsed >= wake_at).await;
+            })
+        }
+    }
+
+    async fn settle() {
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn virtual_clock() {
+        let clock = VirtualClock::new();
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let f = RetryFuture::new(
+            {
+                let attempts = attempts.clone();
+                move || {
+                    let attempts = attempts.clone();
+                    async move {
+                        attempts.fetch_add(1, Ordering::SeqCst);
+                        Err::<(), RetryPolicy>(RetryPolicy::Retry(None))
+                    }
+                }
+            },
+            LinearRetryStrategy::new()
+                .max_attempts(2)
+                .delay_between_retries(Duration::from_secs(60)),
+        )
+        .with_clock(clock.clone());
+        let handle = tokio::spawn(f);
+
+        settle().await;
+        assert_eq!(1, attempts.load(Ordering::SeqCst));
+
+        clock.advance(Duration::from_secs(59));
+        settle().await;
+        assert_eq!(1, attempts.load(Ordering::SeqCst));
+
+        clock.advance(Duration::from_secs(1));
+        settle().await;
+        assert_eq!(2, attempts.load(Ordering::SeqCst));
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(3, handle.await.unwrap().unwrap_err().len());
+        assert_eq!(3, attempts.load(Ordering::SeqCst));
+        assert_eq!(Duration::from_secs(120), clock.now() - clock.start);
+    }
+}
@@ -1,11 +1,57 @@
-use crate::RetryPolicy;
+use crate::timeline::AttemptRecord;
+use crate::{Decision, RetryPolicy};
+use std::any::Any;
 use std::fmt::{Debug, Display, Formatter};
+use std::time::Duration;
 
 /// Used in [retry policy](crate::RetryPolicy) inside
 /// `Retry` variant
+///
+/// ## Backtraces
+///
+/// `anyhow::Error` captures a backtrace when it is created if `RUST_LIB_BACKTRACE`
+/// or `RUST_BACKTRACE` is set, and this cannot be turned off for a single error,
+/// so there is no per-future toggle: even a copy of the message made by
+/// [compact_errors](crate::RetryFuture::compact_errors) captures a new one.
+/// To avoid the cost in hot retry loops while keeping backtraces of panics,
+/// set `RUST_LIB_BACKTRACE=0` before the first error is created.
 pub struct Error {
     pub error: anyhow::Error,
     pub(crate) is_early_returned: bool,
+    pub(crate) retry_after: Option<Duration>,
+    pub(crate) delay_factor: Option<f64>,
+    /// Decision of [inspect](crate::RetryFuture::inspect) overriding the usual handling
+    pub(crate) forced: Option<Decision>,
+    /// Result of the attempt which [inspect](crate::RetryFuture::inspect) decided to retry
+    pub(crate) retried: Option<Retried>,
+}
+
+/// Value of an attempt retried by [inspect](crate::RetryFuture::inspect)
+pub(crate) enum Retried {
+    Ok(RetriedValue),
+    Fail(RetriedValue),
+}
+
+/// Retried value which only has to be `Send`. It is shared only as `&T` of a `Sync` type
+/// and otherwise reached through `&mut`, so that [Error] stays `Sync`.
+pub(crate) struct RetriedValue(Box<dyn Any + Send>);
+
+// SAFETY: `&RetriedValue` gives access to the value only as `&T` where `T: Sync`,
+// see `RetriedValue::get`
+unsafe impl Sync for RetriedValue {}
+
+impl RetriedValue {
+    pub(crate) fn new<T: Send + 'static>(value: T) -> Self {
+        Self(Box::new(value))
+    }
+
+    fn get<T: Sync + 'static>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+
+    fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.0.downcast_mut()
+    }
 }
 
 impl Debug for Error {
@@ -16,11 +62,125 @@ impl Debug for Error {
 
 impl Error {
     pub fn msg<M: Display + Debug + Send + Sync + 'static>(msg: M) -> Self {
-        Self { error: anyhow::Error::msg(msg), is_early_returned: false }
+        Self::from_anyhow(anyhow::Error::msg(msg), false)
     }
 
     pub fn new<E: std::error::Error + Send + Sync + 'static>(e: E) -> Self {
-        Self { error: anyhow::Error::new(e), is_early_returned: false }
+        Self::from_anyhow(anyhow::Error::new(e), false)
+    }
+
+    pub(crate) fn from_anyhow(error: anyhow::Error, is_early_returned: bool) -> Self {
+        Self {
+            error,
+            is_early_returned,
+            retry_after: None,
+            delay_factor: None,
+            forced: None,
+            retried: None,
+        }
+    }
+
+    /// `Ok` value of the attempt which [inspect](crate::RetryFuture::inspect)
+    /// decided to retry with [Decision::RetryNow]
+    pub fn retried_ok<T: Sync + 'static>(&self) -> Option<&T> {
+        match &self.retried {
+            Some(Retried::Ok(value)) => value.get(),
+            _ => None,
+        }
+    }
+
+    /// Same as [retried_ok](Error::retried_ok), but also for values which are not `Sync`
+    pub fn retried_ok_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        match &mut self.retried {
+            Some(Retried::Ok(value)) => value.get_mut(),
+            _ => None,
+        }
+    }
+
+    /// Error of [RetryPolicy::Fail] which [inspect](crate::RetryFuture::inspect)
+    /// decided to retry with [Decision::RetryNow]
+    pub fn retried_fail<E: Sync + 'static>(&self) -> Option<&E> {
+        match &self.retried {
+            Some(Retried::Fail(value)) => value.get(),
+            _ => None,
+        }
+    }
+
+    /// Same as [retried_fail](Error::retried_fail), but also for errors which are not `Sync`
+    pub fn retried_fail_mut<E: 'static>(&mut self) -> Option<&mut E> {
+        match &mut self.retried {
+            Some(Retried::Fail(value)) => value.get_mut(),
+            _ => None,
+        }
+    }
+
+    /// Overrides the delay returned by [retry strategy](crate::retry_strategy::RetryStrategy)
+    /// before the next attempt, e.g. when a server tells how long to back off.
+    ///
+    /// [RetryStop] returned by the strategy is still respected.
+    pub fn retry_after(mut self, delay: Duration) -> Self {
+        self.retry_after = Some(delay);
+        self
+    }
+
+    /// Multiplies the delay returned by [retry strategy](crate::retry_strategy::RetryStrategy)
+    /// before the next attempt by `factor`, e.g. by a load factor reported by a busy server.
+    ///
+    /// [retry_after](Error::retry_after) takes precedence over it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is negative or not finite.
+    pub fn delay_factor(mut self, factor: f64) -> Self {
+        assert!(factor >= 0.0 && factor.is_finite(), "delay factor must be a non-negative number");
+        self.delay_factor = Some(factor);
+        self
+    }
+
+    /// Delay before the next attempt instead of `delay` of the strategy
+    pub(crate) fn delay_hint(&self) -> DelayHint {
+        let retry_after = match self.forced {
+            Some(Decision::RetryNow) => Some(Duration::ZERO),
+            _ => self.retry_after,
+        };
+        DelayHint { retry_after, factor: self.delay_factor }
+    }
+
+    /// `anyhow::Error` is not `Clone`, so the copy keeps only the rendered error chain,
+    /// without the [retried](Error::retried_ok) value
+    pub(crate) fn duplicate(&self) -> Self {
+        Self {
+            error: anyhow::Error::msg(format!("{:#}", self.error)),
+            is_early_returned: self.is_early_returned,
+            retry_after: self.retry_after,
+            delay_factor: self.delay_factor,
+            forced: self.forced,
+            retried: None,
+        }
+    }
+}
+
+/// How an [Error] changes the delay of the strategy, see [Error::retry_after]
+/// and [Error::delay_factor]
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct DelayHint {
+    retry_after: Option<Duration>,
+    factor: Option<f64>,
+}
+
+impl DelayHint {
+    pub(crate) fn of(error: Option<&Error>) -> Self {
+        error.map(Error::delay_hint).unwrap_or_default()
+    }
+
+    pub(crate) fn apply(self, delay: Duration) -> Duration {
+        match (self.retry_after, self.factor) {
+            (Some(retry_after), _) => retry_after,
+            (None, Some(factor)) => {
+                Duration::try_from_secs_f64(delay.as_secs_f64() * factor).unwrap_or(Duration::MAX)
+            }
+            (None, None) => delay,
+        }
     }
 }
 
@@ -28,29 +188,187 @@ impl Error {
 /// when we await [RetryFuture](crate::RetryFuture), the returned type is `Result<T, RetryError<E>>`
 ///
 /// This type accumulates all errors that happen inside inner future.
-/// This means that after a future fails to resolve to Ok(_), an error is pushed to `errors` Vec
+/// This means that after a future fails to resolve to Ok(_), an error is pushed to
+/// [errors](RetryError::errors)
+///
+/// `RetryError<E>` is `Send` and `Sync` if `E` is, so it can be returned from
+/// spawned tasks or converted into `anyhow::Error` and `Box<dyn Error + Send + Sync>`.
 pub struct RetryError<E> {
-    pub errors: Vec<RetryPolicy<E>>,
+    errors: Vec<RetryPolicy<E>>,
+    pub(crate) reason: StopReason,
+    pub(crate) timeline: Vec<AttemptRecord>,
+}
+
+/// Why [RetryFuture](crate::RetryFuture) stopped retrying
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StopReason {
+    /// Inner future returned [RetryPolicy::Fail](crate::RetryPolicy::Fail)
+    Fail,
+    /// Inner future returned an error early by using `?`, but
+    /// [retry strategy](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    /// does not retry such errors
+    EarlyReturned,
+    /// [Retry strategy](crate::retry_strategy::RetryStrategy) returned [RetryStop::MaxAttempts]
+    TooManyAttempts,
+    /// [Retry strategy](crate::retry_strategy::RetryStrategy) returned [RetryStop::Deadline]
+    Deadline,
+    /// [Retry strategy](crate::retry_strategy::RetryStrategy) returned [RetryStop::Budget]
+    Budget,
+    /// Retrying was cancelled, e.g. by a [shutdown flag](crate::RetryFuture::with_shutdown)
+    Cancelled,
+    /// The future passed to [until](crate::RetryFuture::until) completed first
+    DeadlineExceeded,
+    /// The same error was returned too many times in a row,
+    /// see [stop_on_repeated_errors](crate::RetryFuture::stop_on_repeated_errors)
+    RepeatedErrors,
+    /// The hook of [inspect](crate::RetryFuture::inspect) returned
+    /// [Decision::Stop](crate::Decision::Stop)
+    Inspected,
+    /// Pending values made no progress for too many attempts,
+    /// see [stall_detector](crate::RetryFuture::stall_detector)
+    Stalled,
+    /// Like [TooManyAttempts](StopReason::TooManyAttempts), but the last attempt was `Ok`
+    /// and [inspect](crate::RetryFuture::inspect) decided to retry it, see
+    /// [RetryError::last_ok]
+    ExhaustedWhilePending,
+}
+
+impl From<RetryStop> for StopReason {
+    fn from(stop: RetryStop) -> Self {
+        match stop {
+            RetryStop::MaxAttempts => StopReason::TooManyAttempts,
+            RetryStop::Deadline => StopReason::Deadline,
+            RetryStop::Budget => StopReason::Budget,
+        }
+    }
 }
 
 impl<E> RetryError<E> {
+    pub(crate) fn new(errors: Vec<RetryPolicy<E>>, reason: StopReason) -> Self {
+        Self { errors, reason, timeline: Vec::new() }
+    }
+
+    /// Returns why retrying was stopped
+    pub fn reason(&self) -> StopReason {
+        self.reason
+    }
+
+    /// Returns records of failed attempts if
+    /// [record_timeline](crate::RetryFuture::record_timeline) is enabled
+    pub fn timeline(&self) -> &[AttemptRecord] {
+        &self.timeline
+    }
+
+    /// Returns all errors in the order they happened
+    pub fn errors(&self) -> &[RetryPolicy<E>] {
+        &self.errors
+    }
+
+    /// Returns all errors, allowing to modify them
+    pub fn errors_mut(&mut self) -> &mut Vec<RetryPolicy<E>> {
+        &mut self.errors
+    }
+
+    /// Consumes the error, returning all errors
+    pub fn into_errors(self) -> Vec<RetryPolicy<E>> {
+        self.errors
+    }
+
+    /// Returns the number of errors, i.e. failed attempts
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Returns `true` if there are no errors, e.g. retrying was cancelled
+    /// before the first attempt failed
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
     /// Returns the last error encountered
     pub fn last_error(&self) -> Option<&RetryPolicy<E>> {
         self.errors.last()
     }
+
+    /// Returns the last `Ok` value which [inspect](crate::RetryFuture::inspect)
+    /// decided to retry, e.g. the status of a job which is still pending
+    /// when retrying is [exhausted](StopReason::ExhaustedWhilePending)
+    pub fn last_ok<T: Sync + 'static>(&self) -> Option<&T> {
+        self.errors.iter().rev().find_map(|err| match err {
+            RetryPolicy::Retry(Some(e)) => e.retried_ok(),
+            _ => None,
+        })
+    }
+
+    /// Appends errors and [timeline](RetryError::timeline) of `other` to these ones,
+    /// e.g. when a fallback operation has failed after this one.
+    ///
+    /// `other` is considered to happen later, so its [reason](RetryError::reason) is kept.
+    pub fn merge(mut self, other: RetryError<E>) -> RetryError<E> {
+        self.errors.extend(other.errors);
+        self.timeline.extend(other.timeline);
+        self.reason = other.reason;
+        self
+    }
+
+    /// Moves out the error of the last [RetryPolicy::Fail](crate::RetryPolicy::Fail)
+    /// if the future has failed with it, otherwise returns `None`
+    pub fn into_fail(mut self) -> Option<E> {
+        match self.errors.pop() {
+            Some(RetryPolicy::Fail(e)) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<E: Debug> RetryError<E> {
+    /// Number of occurrences of every distinct error, the most frequent first,
+    /// e.g. `[("Retry(connection reset)", 18), ("Retry(timeout)", 2)]`.
+    ///
+    /// Errors are described without backtraces, errors occurring equally often
+    /// are ordered by their first occurrence.
+    pub fn error_histogram(&self) -> Vec<(String, usize)> {
+        let mut histogram: Vec<(String, usize)> = Vec::new();
+        for retry_policy in &self.errors {
+            let summary = retry_policy.summary();
+            match histogram.iter_mut().find(|(error, _)| *error == summary) {
+                Some((_, count)) => *count += 1,
+                None => histogram.push((summary, 1)),
+            }
+        }
+        histogram.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        histogram
+    }
 }
 
+/// Consecutive errors with the same message are shown once, prefixed with their number,
+/// e.g. `(x5) TooManyRetries: ..`
 impl<E: Debug> Display for RetryError<E> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        for (i, retry_policy) in self.errors.iter().enumerate() {
+        let mut i = 0;
+        while i < self.errors.len() {
+            let retry_policy = &self.errors[i];
+            // compared without backtraces, which differ between attempts
+            let summary = retry_policy.summary();
+            let repeated =
+                self.errors[i + 1..].iter().take_while(|other| other.summary() == summary).count();
+            let count = match repeated {
+                0 => String::new(),
+                n => format!("(x{}) ", n + 1),
+            };
             match retry_policy {
                 RetryPolicy::Retry(maybe_error) => {
                     writeln!(f, "{}", "-".repeat(100))?;
-                    writeln!(f, "Attempt {i} ")?;
-                    writeln!(f, "TooManyRetries: {maybe_error:?}")?;
+                    match repeated {
+                        0 => writeln!(f, "Attempt {i} ")?,
+                        n => writeln!(f, "Attempts {i}-{} ", i + n)?,
+                    }
+                    writeln!(f, "{count}TooManyRetries: {maybe_error:?}")?;
                 }
-                RetryPolicy::Fail(fail) => writeln!(f, "Fail: {fail:?}")?,
+                RetryPolicy::Fail(fail) => writeln!(f, "{count}Fail: {fail:?}")?,
             }
+            i += repeated + 1;
         }
         Ok(())
     }
@@ -64,14 +382,288 @@ impl<E: Debug> Debug for RetryError<E> {
 
 impl<E: Debug> std::error::Error for RetryError<E> {}
 
-/// Type to be used in [RetryStrategy](crate::retry_strategy::RetryStrategy)
-#[derive(Debug, Copy, Clone)]
-pub struct TooManyAttempts;
+/// Mostly useful in tests.
+///
+/// `anyhow::Error` is not comparable, so two `RetryPolicy::Retry` are equal if both
+/// either contain an [error](Error) or not, regardless of the error itself.
+/// `RetryPolicy::Fail` payloads are compared as usual. The [reason](RetryError::reason)
+/// and the [timeline](RetryError::timeline) must be equal as well.
+impl<E: PartialEq> PartialEq for RetryError<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.reason == other.reason
+            && self.timeline == other.timeline
+            && self.errors.len() == other.errors.len()
+            && self.errors.iter().zip(&other.errors).all(|pair| match pair {
+                (RetryPolicy::Retry(a), RetryPolicy::Retry(b)) => a.is_some() == b.is_some(),
+                (RetryPolicy::Fail(a), RetryPolicy::Fail(b)) => a == b,
+                _ => false,
+            })
+    }
+}
+
+/// Returned by [RetryStrategy](crate::retry_strategy::RetryStrategy) to tell why
+/// there must be no more attempts
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RetryStop {
+    /// There were already too many attempts
+    MaxAttempts,
+    /// A point in time after which retrying makes no sense has passed
+    Deadline,
+    /// Time or other resources allowed to be spent on retrying are exhausted
+    Budget,
+}
 
-impl Display for TooManyAttempts {
+impl Display for RetryStop {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         <Self as Debug>::fmt(self, f)
     }
 }
 
-impl std::error::Error for TooManyAttempts {}
+impl std::error::Error for RetryStop {}
+
+/// Former error type of [RetryStrategy](crate::retry_strategy::RetryStrategy)
+#[deprecated(note = "use `RetryStop` instead")]
+pub type TooManyAttempts = RetryStop;
+
+/// Allows to keep returning `Err(TooManyAttempts)` from existing strategies
+#[deprecated(note = "use `RetryStop::MaxAttempts` instead")]
+#[allow(non_upper_case_globals)]
+pub const TooManyAttempts: RetryStop = RetryStop::MaxAttempts;
+
+/// Returned when configuration read from an environment variable is invalid,
+/// e.g. by [ExponentialRetryStrategy::from_env](crate::ExponentialRetryStrategy::from_env)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvError {
+    /// Name of the variable
+    pub var: String,
+    /// Value which could not be parsed, lossily converted to UTF-8
+    pub value: String,
+}
+
+impl Display for EnvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid value {:?} of environment variable {}", self.value, self.var)
+    }
+}
+
+impl std::error::Error for EnvError {}
+
+/// Returned by [RetryStrategy::validate](crate::RetryStrategy::validate)
+/// when a strategy is misconfigured, e.g. it would never retry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrategyConfigError {
+    /// [Name](crate::RetryStrategy::name) of the strategy
+    pub strategy: &'static str,
+    /// What is wrong with the configuration
+    pub reason: String,
+}
+
+impl StrategyConfigError {
+    pub(crate) fn new(strategy: &'static str, reason: impl Into<String>) -> Self {
+        Self { strategy, reason: reason.into() }
+    }
+}
+
+impl Display for StrategyConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid configuration of {} retry strategy: {}", self.strategy, self.reason)
+    }
+}
+
+impl std::error::Error for StrategyConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_histogram() {
+        let errors = ["reset", "timeout", "reset", "reset", "timeout", "refused"]
+            .map(|e| RetryPolicy::Retry(Some(Error::msg(e))))
+            .into_iter()
+            .chain([RetryPolicy::Retry(None), RetryPolicy::Fail("fatal")])
+            .collect();
+        let error = RetryError::new(errors, StopReason::Fail);
+        assert_eq!(
+            vec![
+                (String::from("Retry(reset)"), 3),
+                (String::from("Retry(timeout)"), 2),
+                (String::from("Retry(refused)"), 1),
+                (String::from("Retry(None)"), 1),
+                (String::from("Fail(\"fatal\")"), 1),
+            ],
+            error.error_histogram()
+        );
+    }
+
+    #[test]
+    fn display_groups_repeated_errors() {
+        let mut errors: Vec<_> =
+            (0..5).map(|_| RetryPolicy::Retry(Some(Error::msg("timeout")))).collect();
+        errors.push(RetryPolicy::Retry(Some(Error::msg("reset"))));
+        errors.push(RetryPolicy::Fail("fatal"));
+        let display = RetryError::new(errors, StopReason::Fail).to_string();
+        // skip separators and backtraces
+        let lines: Vec<_> = display
+            .lines()
+            .filter(|line| {
+                ["Attempt", "(x", "TooManyRetries", "Fail"].iter().any(|p| line.starts_with(p))
+            })
+            .collect();
+        let expected = [
+            "Attempts 0-4 ",
+            "(x5) TooManyRetries: Some(timeout",
+            "Attempt 5 ",
+            "TooManyRetries: Some(reset",
+            "Fail: \"fatal\"",
+        ];
+        assert_eq!(expected.len(), lines.len(), "{display}");
+        for (line, expected) in lines.iter().zip(expected) {
+            assert!(line.starts_with(expected), "{line}");
+        }
+    }
+
+    #[test]
+    fn into_fail() {
+        let error =
+            RetryError::new(vec![RetryPolicy::Retry(None), RetryPolicy::Fail(5)], StopReason::Fail);
+        assert_eq!(Some(5), error.into_fail());
+    }
+
+    #[test]
+    fn into_fail_without_fail() {
+        let error =
+            RetryError::<u8>::new(vec![RetryPolicy::Retry(None)], StopReason::TooManyAttempts);
+        assert_eq!(None, error.into_fail());
+        let error = RetryError::<u8>::new(vec![], StopReason::Cancelled);
+        assert_eq!(None, error.into_fail());
+    }
+
+    #[test]
+    fn len() {
+        let error = RetryError::<u8>::new(
+            vec![RetryPolicy::Retry(None), RetryPolicy::Retry(None)],
+            StopReason::TooManyAttempts,
+        );
+        assert_eq!(2, error.len());
+        assert!(!error.is_empty());
+
+        let error = RetryError::<u8>::new(vec![], StopReason::Cancelled);
+        assert_eq!(0, error.len());
+        assert!(error.is_empty());
+    }
+
+    #[test]
+    fn accessors() {
+        let mut error =
+            RetryError::new(vec![RetryPolicy::Retry(None), RetryPolicy::Fail(5)], StopReason::Fail);
+        assert_eq!(2, error.errors().len());
+        assert!(matches!(error.errors()[1], RetryPolicy::Fail(5)));
+
+        error.errors_mut().remove(0);
+        assert_eq!(1, error.len());
+
+        let errors = error.into_errors();
+        assert!(matches!(errors.as_slice(), [RetryPolicy::Fail(5)]));
+    }
+
+    #[test]
+    fn eq() {
+        let error = || {
+            RetryError::new(
+                vec![
+                    RetryPolicy::Retry(None),
+                    RetryPolicy::Retry(Some(Error::msg("timeout"))),
+                    RetryPolicy::Fail(5),
+                ],
+                StopReason::Fail,
+            )
+        };
+        assert_eq!(error(), error());
+
+        let mut other = error();
+        other.errors[1] = RetryPolicy::Retry(Some(Error::msg("connection reset")));
+        assert_eq!(error(), other);
+    }
+
+    #[test]
+    fn ne() {
+        let error = || {
+            RetryError::new(vec![RetryPolicy::Retry(None), RetryPolicy::Fail(5)], StopReason::Fail)
+        };
+
+        let mut other = error();
+        other.errors[1] = RetryPolicy::Fail(6);
+        assert_ne!(error(), other);
+
+        let mut other = error();
+        other.errors[0] = RetryPolicy::Retry(Some(Error::msg("timeout")));
+        assert_ne!(error(), other);
+
+        let mut other = error();
+        other.errors.pop();
+        assert_ne!(error(), other);
+
+        let mut other = error();
+        other.reason = StopReason::Cancelled;
+        assert_ne!(error(), other);
+    }
+
+    #[test]
+    fn stop_reason_from_retry_stop() {
+        for (stop, reason) in [
+            (RetryStop::MaxAttempts, StopReason::TooManyAttempts),
+            (RetryStop::Deadline, StopReason::Deadline),
+            (RetryStop::Budget, StopReason::Budget),
+        ] {
+            assert_eq!(reason, StopReason::from(stop));
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn too_many_attempts() {
+        fn check_attempt() -> Result<std::time::Duration, TooManyAttempts> {
+            Err(TooManyAttempts)
+        }
+        assert!(matches!(check_attempt(), Err(RetryStop::MaxAttempts)));
+    }
+
+    #[test]
+    fn merge() {
+        let first = RetryError::new(
+            vec![RetryPolicy::Retry(None), RetryPolicy::Retry(Some(Error::msg("timeout")))],
+            StopReason::TooManyAttempts,
+        );
+        let second = RetryError::new(vec![RetryPolicy::Fail(5)], StopReason::Fail);
+        let error = first.merge(second);
+        assert_eq!(3, error.len());
+        assert!(matches!(error.errors()[0], RetryPolicy::Retry(None)));
+        assert!(matches!(error.errors()[1], RetryPolicy::Retry(Some(_))));
+        assert_eq!(StopReason::Fail, error.reason());
+        assert_eq!(Some(5), error.into_fail());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn send_sync() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<RetryError<String>>();
+        assert_send_sync::<RetryError<std::io::Error>>();
+
+        let error = tokio::spawn(async {
+            let future = crate::RetryFuture::new(
+                || async { Err::<(), _>(RetryPolicy::Fail(String::from("fatal"))) },
+                crate::LinearRetryStrategy::new(),
+            );
+            tokio::spawn(future).await.unwrap().unwrap_err()
+        })
+        .await
+        .unwrap();
+        assert_eq!(StopReason::Fail, error.reason());
+
+        let boxed: Box<dyn std::error::Error + Send + Sync> = Box::new(error);
+        let error = anyhow::Error::from(*boxed.downcast::<RetryError<String>>().unwrap());
+        assert_eq!(StopReason::Fail, error.downcast::<RetryError<String>>().unwrap().reason());
+    }
+}
@@ -0,0 +1,23 @@
+use crate::error::StopReason;
+use std::time::Duration;
+
+/// Event published by [RetryFuture](crate::RetryFuture) to the
+/// [event bus](crate::RetryFuture::with_event_bus)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RetryEvent {
+    /// An attempt is about to start, `attempt` is the number of failed attempts before it
+    AttemptStarted { attempt: usize },
+    /// An attempt failed
+    AttemptFailed {
+        attempt: usize,
+        /// Description of the error without backtrace
+        error: String,
+        /// Delay before the next attempt or `None` if retrying stops
+        delay: Option<Duration>,
+    },
+    /// An attempt succeeded
+    Succeeded { attempt: usize },
+    /// Retrying stopped without success
+    GaveUp { reason: StopReason },
+}
@@ -1,26 +1,373 @@
+use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
-use futures::{ready, TryFuture};
+use futures::future::{BoxFuture, Either, IntoFuture, MapErr};
+use futures::stream::FuturesUnordered;
+use futures::{ready, Stream, StreamExt, TryFuture, TryFutureExt};
 use pin_project::pin_project;
-use tokio::time::sleep;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::{AcquireError, OwnedSemaphorePermit, Semaphore};
 
-use crate::error::RetryError;
-use crate::retry_strategy::RetryStrategy;
+use crate::clock::{Clock, TokioClock};
+use crate::error::{
+    DelayHint, Error, Retried, RetriedValue, RetryError, RetryStop, StopReason, StrategyConfigError,
+};
+use crate::events::RetryEvent;
+use crate::handle::RetryHandle;
+use crate::retry_strategy::{AsyncDecision, RetryStrategy};
+use crate::timeline::{AttemptRecord, Timeline};
 use crate::RetryPolicy;
 
+/// Creates a new [future](FutureFactory::Future) for every attempt of [RetryFuture].
+///
+/// It is implemented for every `FnMut() -> Future` closure, so usually
+/// there is no need to implement it yourself.
+pub trait FutureFactory {
+    type Future;
+
+    /// `attempts_before` is the number of failed attempts so far
+    fn new_future(&mut self, attempts_before: usize) -> Self::Future;
+}
+
+impl<F, Fut> FutureFactory for F
+where
+    F: FnMut() -> Fut,
+{
+    type Future = Fut;
+
+    fn new_future(&mut self, _attempts_before: usize) -> Self::Future {
+        self()
+    }
+}
+
+/// [FutureFactory] which alternates between two factories,
+/// see [RetryFuture::new_failover]
+pub struct Failover<P, S> {
+    primary: P,
+    secondary: S,
+}
+
+impl<P, S, FutP, FutS> FutureFactory for Failover<P, S>
+where
+    P: FnMut() -> FutP,
+    S: FnMut() -> FutS,
+{
+    type Future = Either<FutP, FutS>;
+
+    fn new_future(&mut self, attempts_before: usize) -> Self::Future {
+        if attempts_before.is_multiple_of(2) {
+            Either::Left((self.primary)())
+        } else {
+            Either::Right((self.secondary)())
+        }
+    }
+}
+
+/// [FutureFactory] which runs every attempt on the blocking thread pool,
+/// see [RetryFuture::new_blocking]
+#[cfg(feature = "blocking")]
+pub struct Blocking<F>(Arc<std::sync::Mutex<F>>);
+
+#[cfg(feature = "blocking")]
+type BlockingAttempt<T, E> = futures::future::Map<
+    tokio::task::JoinHandle<Result<T, RetryPolicy<E>>>,
+    fn(Result<Result<T, RetryPolicy<E>>, tokio::task::JoinError>) -> Result<T, RetryPolicy<E>>,
+>;
+
+#[cfg(feature = "blocking")]
+impl<F, T, E> FutureFactory for Blocking<F>
+where
+    F: FnMut() -> Result<T, RetryPolicy<E>> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+{
+    type Future = BlockingAttempt<T, E>;
+
+    fn new_future(&mut self, _attempts_before: usize) -> Self::Future {
+        use futures::FutureExt;
+
+        let f = self.0.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut f = f.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            f()
+        })
+        .map(|result| match result {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(e) => Err(RetryPolicy::Retry(Some(Error::new(e)))),
+        })
+    }
+}
+
+/// [FutureFactory] which passes `Ok` values of attempts through a fallible function,
+/// see [RetryFuture::and_then]
+pub struct AndThen<F, G> {
+    factory: F,
+    f: Arc<std::sync::Mutex<G>>,
+}
+
+impl<F, G, Fut, U> FutureFactory for AndThen<F, G>
+where
+    F: FutureFactory<Future = Fut>,
+    Fut: TryFuture,
+    G: FnMut(Fut::Ok) -> Result<U, Fut::Error>,
+{
+    type Future = AndThenAttempt<Fut, G>;
+
+    fn new_future(&mut self, attempts_before: usize) -> Self::Future {
+        AndThenAttempt { future: self.factory.new_future(attempts_before), f: self.f.clone() }
+    }
+}
+
+/// Attempt of [AndThen] factory
+#[pin_project]
+pub struct AndThenAttempt<Fut, G> {
+    #[pin]
+    future: Fut,
+    f: Arc<std::sync::Mutex<G>>,
+}
+
+impl<Fut, G, U> Future for AndThenAttempt<Fut, G>
+where
+    Fut: TryFuture,
+    G: FnMut(Fut::Ok) -> Result<U, Fut::Error>,
+{
+    type Output = Result<U, Fut::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let t = ready!(this.future.try_poll(cx))?;
+        let mut f = this.f.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        Poll::Ready(f(t))
+    }
+}
+
+/// What [RetryFuture::inspect] does with the result of an attempt
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// Handles the result as usual
+    Continue,
+    /// Retries without a delay, even on success or [RetryPolicy::Fail]
+    RetryNow,
+    /// Stops retrying with [StopReason::Inspected], returning `Ok` and
+    /// [RetryPolicy::Fail] as usual
+    Stop,
+}
+
+/// [FutureFactory] which lets a hook override how results of attempts are handled,
+/// see [RetryFuture::inspect]
+pub struct Inspect<F, G> {
+    factory: F,
+    f: Arc<std::sync::Mutex<G>>,
+}
+
+impl<F, G, Fut, T, E> FutureFactory for Inspect<F, G>
+where
+    F: FutureFactory<Future = Fut>,
+    Fut: TryFuture<Ok = T, Error = RetryPolicy<E>>,
+    T: Send + 'static,
+    E: Debug + Send + 'static,
+    G: FnMut(&Result<T, RetryPolicy<E>>) -> Decision,
+{
+    type Future = InspectAttempt<Fut, G>;
+
+    fn new_future(&mut self, attempts_before: usize) -> Self::Future {
+        InspectAttempt { future: self.factory.new_future(attempts_before), f: self.f.clone() }
+    }
+}
+
+/// Attempt of [Inspect] factory
+#[pin_project]
+pub struct InspectAttempt<Fut, G> {
+    #[pin]
+    future: Fut,
+    f: Arc<std::sync::Mutex<G>>,
+}
+
+impl<Fut, G, T, E> Future for InspectAttempt<Fut, G>
+where
+    Fut: TryFuture<Ok = T, Error = RetryPolicy<E>>,
+    T: Send + 'static,
+    E: Debug + Send + 'static,
+    G: FnMut(&Result<T, RetryPolicy<E>>) -> Decision,
+{
+    type Output = Result<T, RetryPolicy<E>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = ready!(this.future.try_poll(cx));
+        let mut f = this.f.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let forced = |e: Option<Error>, decision| {
+            let mut e = e.unwrap_or_else(|| Error::msg(format!("inspect decided {decision:?}")));
+            e.forced = Some(decision);
+            Err(RetryPolicy::Retry(Some(e)))
+        };
+        Poll::Ready(match (f(&result), result) {
+            (Decision::RetryNow, Ok(t)) => {
+                let mut e = Error::msg("inspect decided RetryNow");
+                e.retried = Some(Retried::Ok(RetriedValue::new(t)));
+                forced(Some(e), Decision::RetryNow)
+            }
+            (Decision::RetryNow, Err(RetryPolicy::Fail(fail))) => {
+                let mut e = Error::msg(format!("{fail:?}"));
+                e.retried = Some(Retried::Fail(RetriedValue::new(fail)));
+                forced(Some(e), Decision::RetryNow)
+            }
+            (Decision::RetryNow, Err(RetryPolicy::Retry(e))) => forced(e, Decision::RetryNow),
+            (Decision::Stop, Err(RetryPolicy::Retry(e))) => forced(e, Decision::Stop),
+            (_, result) => result,
+        })
+    }
+}
+
+type AcquirePermit =
+    Pin<Box<dyn Future<Output = Result<OwnedSemaphorePermit, AcquireError>> + Send>>;
+type ErrorHook<E> = Box<dyn FnMut(&RetryPolicy<E>) + Send>;
+type Cleanup = Box<dyn FnMut() -> BoxFuture<'static, ()> + Send>;
+type AttemptGuard<E> = Box<dyn FnMut() -> Result<(), E> + Send>;
+type Classifier<E> = Box<dyn Fn(&anyhow::Error) -> RetryPolicy<E> + Send>;
+type StallCheck = Box<dyn Fn(&mut Error, &mut Error) -> bool + Send>;
+type ErrorMatcher<E> = Box<dyn Fn(&RetryPolicy<E>) -> bool + Send>;
+type StrategyRoute<E> = (ErrorMatcher<E>, Box<dyn RetryStrategy + Send>);
+
+/// See [RetryFuture::max_attempts_for]. Every budget counts an error,
+/// even if an earlier one is already exceeded by it.
+struct ErrorBudget<E> {
+    matches: ErrorMatcher<E>,
+    max: usize,
+    spent: usize,
+}
+
+impl<E> ErrorBudget<E> {
+    /// Counts `err` if it matches, returning `true` once the budget is exceeded
+    fn spend(&mut self, err: &RetryPolicy<E>) -> bool {
+        if (self.matches)(err) {
+            self.spent += 1;
+        }
+        self.spent > self.max
+    }
+}
+
 #[pin_project(project = FutureStateProj)]
-enum FutureState<Fut> {
+enum FutureState<Fut, S> {
+    /// A new attempt is about to start
+    Start,
+    /// Waiting for a permit of [RetryFuture::with_permit]
+    Acquiring { acquire: AcquirePermit },
     WaitingForFuture {
         #[pin]
         future: Fut,
+        /// See [RetryFuture::attempt_timeout_from_strategy]
+        #[pin]
+        timeout: Option<S>,
     },
+    /// Waiting for [check_attempt_async](RetryStrategy::check_attempt_async)
+    Deciding { pending: PendingDecision },
+    /// Waiting for [RetryFuture::before_retry] ahead of the delay
+    CleaningUp { cleanup: BoxFuture<'static, ()>, delay: Duration },
     TimerActive {
         #[pin]
-        delay: tokio::time::Sleep,
+        delay: S,
     },
+    Hedging {
+        in_flight: FuturesUnordered<IntoFuture<Fut>>,
+        #[pin]
+        hedge: Option<S>,
+    },
+}
+
+/// Decision of [check_attempt_async](RetryStrategy::check_attempt_async) with what
+/// is needed from the error it was asked for, which may be gone once it is awaited
+struct PendingDecision {
+    decision: AsyncDecision,
+    hint: DelayHint,
+    /// Summary of the error for the [timeline](RetryFuture::record_timeline)
+    summary: Option<String>,
+}
+
+/// See [RetryFuture::on_cancel]
+struct CancelHook {
+    callback: Option<Box<dyn FnOnce() + Send>>,
+    /// Whether the future is sleeping between attempts
+    armed: bool,
+}
+
+impl Drop for CancelHook {
+    fn drop(&mut self) {
+        if let (true, Some(callback)) = (self.armed, self.callback.take()) {
+            callback();
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Hedging {
+    delay: Duration,
+    max_in_flight: usize,
+}
+
+/// Everything except the factory, the clock and the state machine itself,
+/// so that it can be borrowed while the state is projected
+struct Core<RS, E> {
+    retry_strategy: RS,
+    attempts_before: usize,
+    errors: Vec<RetryPolicy<E>>,
+    /// See [RetryFuture::error_sink] and [RetryFuture::share_errors]
+    error_hooks: Vec<ErrorHook<E>>,
+    shutdown: Option<Arc<AtomicBool>>,
+    timeline: Option<Timeline>,
+    hedging: Option<Hedging>,
+    before_attempt: Option<AttemptGuard<E>>,
+    classifier: Option<Classifier<E>>,
+    routes: Vec<StrategyRoute<E>>,
+    budgets: Vec<ErrorBudget<E>>,
+    delay_before_first_attempt: Option<Duration>,
+    until: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+    semaphore: Option<Arc<Semaphore>>,
+    /// Held while an attempt is running
+    permit: Option<OwnedSemaphorePermit>,
+    on_success: Option<Box<dyn FnOnce(usize) + Send>>,
+    before_retry: Option<Cleanup>,
+    max_early_return_retries: Option<usize>,
+    stop_on_repeated_errors: Option<usize>,
+    /// See [RetryFuture::stall_detector]
+    stall_detector: Option<(usize, StallCheck)>,
+    /// Number of errors in a row without progress, `None` if there is no previous
+    /// error to compare with
+    stalls: Option<usize>,
+    /// See [RetryFuture::require_streak]
+    required_streak: usize,
+    streak: usize,
+    event_bus: Option<broadcast::Sender<RetryEvent>>,
+    /// Summaries of the last errors for [RetryFuture::stop_on_repeated_errors]
+    recent_errors: VecDeque<String>,
+    /// See [RetryFuture::reset_backoff_after]
+    reset_backoff_after: Option<Duration>,
+    last_failure: Option<Instant>,
+    early_returned: usize,
+    compact_errors: bool,
+    catch_factory_panics: bool,
+    annotate_exhaustion: bool,
+    yield_between_attempts: bool,
+    on_cancel: Option<CancelHook>,
+    attempt_timeout_from_strategy: bool,
+    /// Timeout of the attempt in progress, see [RetryFuture::attempt_timeout_from_strategy]
+    attempt_timeout: Option<Duration>,
+    /// Decision of [check_attempt_async](RetryStrategy::check_attempt_async) to await
+    deciding: Option<PendingDecision>,
+    /// See [RetryFuture::in_span]
+    #[cfg(feature = "tracing")]
+    span: Option<tracing::Span>,
+    /// Child of `span` for the attempt in progress
+    #[cfg(feature = "tracing")]
+    attempt_span: Option<tracing::Span>,
 }
 
 /// A future which is trying to resolve inner future
@@ -30,35 +377,763 @@ enum FutureState<Fut> {
 /// and construct a helper type or use one of existing which implements
 /// [RetryStrategy](crate::retry_strategy::RetryStrategy) trait
 /// which is responsible for configuring retry mechanism
+///
+/// Delays between attempts are measured by a [Clock](crate::clock::Clock),
+/// which is [TokioClock](crate::clock::TokioClock) unless replaced with
+/// [with_clock](RetryFuture::with_clock)
 #[pin_project]
-pub struct RetryFuture<F, Fut, RS, E> {
+pub struct RetryFuture<F, Fut, RS, E, C: Clock = TokioClock> {
     factory: F,
-    retry_strategy: RS,
-    attempts_before: usize,
+    clock: C,
+    /// The first attempt created by [new](RetryFuture::new), which is moved into
+    /// the state once it is started. It is never polled before, so it is not pinned
+    first: Option<Fut>,
     #[pin]
-    state: FutureState<Fut>,
-    errors: Vec<RetryPolicy<E>>,
+    state: FutureState<Fut, C::Sleep>,
+    core: Core<RS, E>,
 }
 
 impl<F, Fut, RS, E> RetryFuture<F, Fut, RS, E>
 where
-    F: FnMut() -> Fut,
+    F: FutureFactory<Future = Fut>,
 {
+    /// Creates the first attempt by calling `factory` right away. The attempt is
+    /// polled once the returned future is, so options which apply before an attempt,
+    /// e.g. [delay_before_first_attempt](RetryFuture::delay_before_first_attempt),
+    /// still apply to it, but [before_attempt](RetryFuture::before_attempt) and
+    /// [catch_factory_panics](RetryFuture::catch_factory_panics) only apply to later calls.
     pub fn new(mut factory: F, retry_strategy: RS) -> Self {
-        let future = factory();
+        let first = factory.new_future(0);
         Self {
             factory,
-            retry_strategy,
-            state: FutureState::WaitingForFuture { future },
-            attempts_before: 0,
-            errors: Vec::new(),
+            clock: TokioClock,
+            first: Some(first),
+            state: FutureState::Start,
+            core: Core {
+                retry_strategy,
+                attempts_before: 0,
+                errors: Vec::new(),
+                error_hooks: Vec::new(),
+                shutdown: None,
+                timeline: None,
+                hedging: None,
+                before_attempt: None,
+                classifier: None,
+                routes: Vec::new(),
+                budgets: Vec::new(),
+                delay_before_first_attempt: None,
+                until: None,
+                semaphore: None,
+                permit: None,
+                on_success: None,
+                before_retry: None,
+                max_early_return_retries: None,
+                stop_on_repeated_errors: None,
+                stall_detector: None,
+                stalls: None,
+                required_streak: 1,
+                streak: 0,
+                event_bus: None,
+                recent_errors: VecDeque::new(),
+                reset_backoff_after: None,
+                last_failure: None,
+                early_returned: 0,
+                compact_errors: false,
+                catch_factory_panics: false,
+                annotate_exhaustion: false,
+                yield_between_attempts: false,
+                on_cancel: None,
+                attempt_timeout_from_strategy: false,
+                attempt_timeout: None,
+                deciding: None,
+                #[cfg(feature = "tracing")]
+                span: None,
+                #[cfg(feature = "tracing")]
+                attempt_span: None,
+            },
         }
     }
 }
 
-impl<F, Fut, RS, E> Future for RetryFuture<F, Fut, RS, E>
+impl<F, Fut, RS, E> RetryFuture<F, Fut, RS, E>
 where
-    F: FnMut() -> Fut,
+    F: FutureFactory<Future = Fut>,
+    RS: RetryStrategy,
+{
+    /// Like [new](RetryFuture::new), but returns an error if the strategy is
+    /// [misconfigured](RetryStrategy::validate), e.g. it would never retry
+    pub fn try_new(factory: F, retry_strategy: RS) -> Result<Self, StrategyConfigError> {
+        retry_strategy.validate()?;
+        Ok(Self::new(factory, retry_strategy))
+    }
+}
+
+impl<F, Fut, E> RetryFuture<F, Fut, Box<dyn RetryStrategy + Send>, E>
+where
+    F: FutureFactory<Future = Fut>,
+{
+    /// Creates a future retried according to the [default_strategy](crate::default_strategy)
+    /// at the moment of the call
+    pub fn with_default(factory: F) -> Self {
+        Self::new(factory, crate::default_strategy())
+    }
+}
+
+impl<P, S, FutP, FutS, RS, E> RetryFuture<Failover<P, S>, Either<FutP, FutS>, RS, E>
+where
+    P: FnMut() -> FutP,
+    S: FnMut() -> FutS,
+{
+    /// Runs `primary` factory on even attempts and `secondary` on odd ones,
+    /// so that a downed primary endpoint does not block all retries
+    pub fn new_failover(primary: P, secondary: S, retry_strategy: RS) -> Self {
+        Self::new(Failover { primary, secondary }, retry_strategy)
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<G, T, RS, E> RetryFuture<Blocking<G>, BlockingAttempt<T, E>, RS, E>
+where
+    G: FnMut() -> Result<T, RetryPolicy<E>> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+{
+    /// Runs every attempt of CPU-bound or blocking `f` with
+    /// [spawn_blocking](tokio::task::spawn_blocking), so that it does not block the executor.
+    ///
+    /// A panic inside `f` is propagated. If the blocking task is cancelled,
+    /// e.g. because the runtime is shutting down, the attempt is retried.
+    /// It must be called within a tokio runtime, as the first attempt is spawned right away.
+    pub fn new_blocking(f: G, retry_strategy: RS) -> Self {
+        Self::new(Blocking(Arc::new(std::sync::Mutex::new(f))), retry_strategy)
+    }
+}
+
+impl<F, Fut, RS, E, C: Clock> RetryFuture<F, Fut, RS, E, C> {
+    /// Replaces the [clock](crate::clock::Clock) used to sleep between attempts
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> RetryFuture<F, Fut, RS, E, C2> {
+        let state = match self.state {
+            FutureState::Start => FutureState::Start,
+            _ => panic!("clock cannot be replaced after RetryFuture is polled"),
+        };
+        RetryFuture { factory: self.factory, clock, first: self.first, state, core: self.core }
+    }
+
+    /// Passes the `Ok` value of every attempt through `f`, e.g. to parse a response,
+    /// so that an error returned by `f` is handled like an error of the attempt:
+    /// [Retry](RetryPolicy::Retry) starts a new attempt and [Fail](RetryPolicy::Fail) gives up.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use retry_future::{Error, LinearRetryStrategy, RetryFuture, RetryPolicy};
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let number = RetryFuture::new(
+    ///     || async { Ok::<_, RetryPolicy<()>>("42") },
+    ///     LinearRetryStrategy::new(),
+    /// )
+    /// .and_then(|body| body.parse::<u32>().map_err(|e| RetryPolicy::Retry(Some(Error::new(e)))))
+    /// .await
+    /// .unwrap();
+    /// assert_eq!(42, number);
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the future has already been polled.
+    pub fn and_then<G, U>(
+        self,
+        f: G,
+    ) -> RetryFuture<AndThen<F, G>, AndThenAttempt<Fut, G>, RS, E, C>
+    where
+        Fut: TryFuture,
+        G: FnMut(Fut::Ok) -> Result<U, Fut::Error>,
+    {
+        let state = match self.state {
+            FutureState::Start => FutureState::Start,
+            _ => panic!("and_then cannot be applied after RetryFuture is polled"),
+        };
+        let f = Arc::new(std::sync::Mutex::new(f));
+        let first = self.first.map(|future| AndThenAttempt { future, f: f.clone() });
+        let factory = AndThen { factory: self.factory, f };
+        RetryFuture { factory, clock: self.clock, first, state, core: self.core }
+    }
+
+    /// Calls `f` with the result of every attempt, which may override how it is handled,
+    /// see [Decision].
+    ///
+    /// Forced retries are recorded as errors and count as attempts of the retry strategy,
+    /// but run without a delay. The retried `Ok` value or error of
+    /// [Fail](RetryPolicy::Fail) is kept in the recorded [Error], see [Error::retried_ok]
+    /// and [Error::retried_fail].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the future has already been polled.
+    pub fn inspect<G, T>(self, f: G) -> RetryFuture<Inspect<F, G>, InspectAttempt<Fut, G>, RS, E, C>
+    where
+        Fut: TryFuture<Ok = T, Error = RetryPolicy<E>>,
+        T: Send + 'static,
+        E: Send + 'static,
+        G: FnMut(&Result<T, RetryPolicy<E>>) -> Decision,
+    {
+        let state = match self.state {
+            FutureState::Start => FutureState::Start,
+            _ => panic!("inspect cannot be applied after RetryFuture is polled"),
+        };
+        let f = Arc::new(std::sync::Mutex::new(f));
+        let first = self.first.map(|future| InspectAttempt { future, f: f.clone() });
+        let factory = Inspect { factory: self.factory, f };
+        RetryFuture { factory, clock: self.clock, first, state, core: self.core }
+    }
+
+    /// Stops retrying as soon as `shutdown` is set to `true`.
+    ///
+    /// The flag is checked before sleeping and before starting a new attempt.
+    /// The returned [error](crate::error::RetryError) has
+    /// [Cancelled](crate::error::StopReason::Cancelled) reason.
+    pub fn with_shutdown(mut self, shutdown: Arc<AtomicBool>) -> Self {
+        self.core.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Sleeps before the first attempt, which otherwise runs immediately on the first poll.
+    /// The first attempt is still created by [new](RetryFuture::new), only polling it is delayed.
+    pub fn delay_before_first_attempt(mut self, delay: Duration) -> Self {
+        self.core.delay_before_first_attempt = Some(delay);
+        self
+    }
+
+    /// Stops retrying as soon as `deadline` completes, e.g. a shutdown signal
+    /// or a timeout of a parent operation, dropping the attempt in progress.
+    ///
+    /// [RetryError::reason](crate::RetryError::reason) is
+    /// [DeadlineExceeded](crate::StopReason::DeadlineExceeded) then.
+    pub fn until(mut self, deadline: impl Future + Send + 'static) -> Self {
+        self.core.until = Some(Box::pin(async move {
+            deadline.await;
+        }));
+        self
+    }
+
+    /// Acquires a permit of `semaphore` before every attempt and releases it after,
+    /// so that attempts of all futures sharing it are limited, see [RetryPool](crate::RetryPool).
+    ///
+    /// If the semaphore is closed, retrying stops with
+    /// [Cancelled](crate::StopReason::Cancelled). Hedged attempts do not acquire permits.
+    pub fn with_permit(mut self, semaphore: Arc<Semaphore>) -> Self {
+        self.core.semaphore = Some(semaphore);
+        self
+    }
+
+    /// Starts counting failed attempts from `attempts_before` instead of zero,
+    /// e.g. to resume retrying after a restart with the delays it would have reached.
+    ///
+    /// The offset is passed to the [retry strategy](RetryStrategy) and the
+    /// [factory](FutureFactory), so it counts towards the maximum number of attempts.
+    pub fn starting_attempt(mut self, attempts_before: usize) -> Self {
+        self.core.attempts_before = attempts_before;
+        self
+    }
+
+    /// Calls `f` with the number of failed attempts when the future resolves to `Ok(_)`,
+    /// e.g. to record it to metrics without changing the output.
+    ///
+    /// Unlike [record_outcome](crate::RetryStrategy::record_outcome) of a strategy,
+    /// it is called once per this future.
+    pub fn on_success(mut self, f: impl FnOnce(usize) + Send + 'static) -> Self {
+        self.core.on_success = Some(Box::new(f));
+        self
+    }
+
+    /// Awaits `f` after every failed attempt which is retried, before sleeping,
+    /// e.g. to close a broken connection or clear a cache.
+    ///
+    /// With [hedging](RetryFuture::hedged), `f` is called only once no attempt is in flight.
+    pub fn before_retry<G, Cf>(mut self, mut f: G) -> Self
+    where
+        G: FnMut() -> Cf + Send + 'static,
+        Cf: Future<Output = ()> + Send + 'static,
+    {
+        self.core.before_retry = Some(Box::new(move || Box::pin(f())));
+        self
+    }
+
+    /// Stops with [StopReason::EarlyReturned] once more than `max` errors returned early
+    /// by using `?` were retried, so that a factory which always fails the same way
+    /// does not consume all attempts of the
+    /// [retry strategy](crate::retry_strategy::RetryStrategy).
+    ///
+    /// Has no effect if the strategy does not
+    /// [retry early returned errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors).
+    pub fn max_early_return_retries(mut self, max: usize) -> Self {
+        self.core.max_early_return_retries = Some(max);
+        self
+    }
+
+    /// Stops with [StopReason::RepeatedErrors] once the last `n` errors are identical,
+    /// e.g. when an error which can never go away is retried by mistake.
+    ///
+    /// Errors are compared by their `Debug` representation without backtraces.
+    /// A success, or an `Ok` value retried by [inspect](RetryFuture::inspect), starts over.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is less than `2`, as a single error is always identical to itself.
+    pub fn stop_on_repeated_errors(mut self, n: usize) -> Self {
+        assert!(n >= 2, "stop_on_repeated_errors needs at least 2 errors to compare");
+        self.core.stop_on_repeated_errors = Some(n);
+        self
+    }
+
+    /// Stops with [StopReason::Stalled] once `no_progress` reports `n` times in a row that
+    /// an `Ok` value made no progress since the previous attempt, e.g. when the status
+    /// of a polled job never changes.
+    ///
+    /// Only consecutive values which [inspect](RetryFuture::inspect) decided to retry
+    /// with [Decision::RetryNow] are compared, other errors are never considered stalled.
+    pub fn stall_detector(
+        mut self,
+        n: usize,
+        no_progress: impl Fn(&Fut::Ok, &Fut::Ok) -> bool + Send + 'static,
+    ) -> Self
+    where
+        Fut: TryFuture,
+        Fut::Ok: 'static,
+    {
+        let check = move |previous: &mut Error, next: &mut Error| match (
+            previous.retried_ok_mut::<Fut::Ok>(),
+            next.retried_ok_mut::<Fut::Ok>(),
+        ) {
+            (Some(previous), Some(next)) => no_progress(previous, next),
+            _ => false,
+        };
+        self.core.stall_detector = Some((n, Box::new(check)));
+        self
+    }
+
+    /// Starts the backoff and the limit of attempts over, i.e. passes zero `attempts_before`
+    /// to the strategy, if more than `quiet` has passed since the previous failure, e.g. when
+    /// a long-lived connection breaks after working for hours.
+    ///
+    /// `quiet` should be longer than the longest delay of the strategy,
+    /// since time between failures includes delays. Collected errors are kept.
+    pub fn reset_backoff_after(mut self, quiet: Duration) -> Self {
+        self.core.reset_backoff_after = Some(quiet);
+        self
+    }
+
+    /// Publishes [events](RetryEvent) about attempts to `sender`, e.g. to monitor
+    /// all retries of a service in one place by sharing the sender between futures.
+    ///
+    /// Publishing never blocks: events are dropped if there are no receivers,
+    /// and lagging receivers miss the oldest events.
+    pub fn with_event_bus(mut self, sender: broadcast::Sender<RetryEvent>) -> Self {
+        self.core.event_bus = Some(sender);
+        self
+    }
+
+    /// Resolves only after `k` attempts in a row succeed, e.g. to wait until
+    /// a recovering dependency is healthy rather than lucky. The value of the last
+    /// attempt is returned.
+    ///
+    /// A failure starts the streak over and is retried as usual. Failures are counted
+    /// by the [retry strategy](RetryStrategy) across successes which are not enough yet.
+    pub fn require_streak(mut self, k: usize) -> Self {
+        self.core.required_streak = k;
+        self
+    }
+
+    /// Calls `f` if the future is dropped while sleeping before the next attempt,
+    /// e.g. to count operations abandoned during backoff on shutdown.
+    ///
+    /// It is not called if the future is dropped after it resolved or while an attempt is running.
+    pub fn on_cancel(mut self, f: impl FnOnce() + Send + 'static) -> Self {
+        self.core.on_cancel = Some(CancelHook { callback: Some(Box::new(f)), armed: false });
+        self
+    }
+
+    /// Creates a `retry_attempt` child span of `span` for every attempt, which is entered
+    /// while the attempt is polled and has `attempt`, `delay` and `outcome` fields.
+    ///
+    /// The final outcome is recorded on `span` into `retry.outcome` and `retry.attempts`
+    /// fields if they are declared. With `tracing-opentelemetry` attempts become child
+    /// spans of the OpenTelemetry span of the caller. Hedged attempts are not traced separately.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use retry_future::{LinearRetryStrategy, RetryFuture, RetryPolicy};
+    ///
+    /// # async fn run() {
+    /// let span = tracing::info_span!(
+    ///     "fetch_user",
+    ///     retry.outcome = tracing::field::Empty,
+    ///     retry.attempts = tracing::field::Empty,
+    /// );
+    /// let result = RetryFuture::new(
+    ///     || async { Ok::<_, RetryPolicy<()>>(()) },
+    ///     LinearRetryStrategy::new(),
+    /// )
+    /// .in_span(span)
+    /// .await;
+    /// # }
+    /// ```
+    #[cfg(feature = "tracing")]
+    pub fn in_span(mut self, span: tracing::Span) -> Self {
+        self.core.span = Some(span);
+        self
+    }
+
+    /// Yields to the executor before every retry, even if the delay has already
+    /// passed, so that other tasks are not starved by a burst of quick retries.
+    /// Retries without delay always yield.
+    pub fn yield_between_attempts(mut self) -> Self {
+        self.core.yield_between_attempts = true;
+        self
+    }
+
+    /// Stores only the rendered message of every [Error](crate::Error) instead of the
+    /// original error, so its sources are dropped right away. Useful to bound memory
+    /// in long retry loops. Values retried by [inspect](RetryFuture::inspect) are kept.
+    ///
+    /// Backtraces are still captured, see [backtraces](crate::Error#backtraces).
+    pub fn compact_errors(mut self) -> Self {
+        self.core.compact_errors = true;
+        self
+    }
+
+    /// Turns a panic of the [factory](FutureFactory) itself, not of the future it returned,
+    /// into a retryable [Error] with the panic message, so the
+    /// [retry strategy](RetryStrategy) decides whether to try again.
+    ///
+    /// The factory is called inside [catch_unwind](std::panic::catch_unwind) wrapped into
+    /// [AssertUnwindSafe](std::panic::AssertUnwindSafe), so no `UnwindSafe` bound is required.
+    /// It is up to the caller to make sure that a factory which panicked halfway,
+    /// e.g. while holding a `RefCell` borrow or mutating captured state, is still fine to call.
+    /// The panic hook runs as usual, and nothing is caught with `panic = "abort"`.
+    /// The first call happens in [new](RetryFuture::new), so a panic there is not caught.
+    pub fn catch_factory_panics(mut self) -> Self {
+        self.core.catch_factory_panics = true;
+        self
+    }
+
+    /// When the [retry strategy](RetryStrategy) gives up, appends
+    /// `Retry` with an [Error] such as "exceeded max attempts" to the errors,
+    /// so the list explains by itself why retrying stopped.
+    ///
+    /// The entry is not an attempt, it is not passed to the [error_sink](RetryFuture::error_sink)
+    /// nor recorded in the [timeline](RetryFuture::record_timeline).
+    pub fn annotate_exhaustion(mut self) -> Self {
+        self.core.annotate_exhaustion = true;
+        self
+    }
+
+    /// Limits every attempt by the delay which would follow it if it failed,
+    /// i.e. "try for as long as you would wait". A timed out attempt is retried.
+    ///
+    /// The delay is taken from [peek_next_delay](crate::RetryStrategy::peek_next_delay)
+    /// of the main [retry strategy](crate::retry_strategy::RetryStrategy), so the state of
+    /// the strategy is not changed before the attempt. If the strategy cannot tell the delay
+    /// in advance, or there are too many attempts, the attempt is not limited.
+    /// Hedged attempts are not limited.
+    pub fn attempt_timeout_from_strategy(mut self) -> Self {
+        self.core.attempt_timeout_from_strategy = true;
+        self
+    }
+
+    /// Records an [AttemptRecord](crate::timeline::AttemptRecord) for every failed attempt.
+    ///
+    /// Records are available from [RetryError::timeline](crate::error::RetryError::timeline)
+    /// or, also on success, from the [timeline](RetryFuture::timeline) handle.
+    pub fn record_timeline(mut self) -> Self {
+        self.core.timeline.get_or_insert_with(Timeline::default);
+        self
+    }
+
+    /// Handle to the records of [record_timeline](RetryFuture::record_timeline),
+    /// which can be read after the future is consumed.
+    ///
+    /// Returns `None` if recording is not enabled.
+    pub fn timeline(&self) -> Option<Timeline> {
+        self.core.timeline.clone()
+    }
+}
+
+impl<F, Fut, RS, E, C: Clock> RetryFuture<F, Fut, RS, E, C> {
+    /// Checks a precondition before each attempt after the first one, e.g. whether a dependency
+    /// is healthy. The first attempt is created by [new](RetryFuture::new) before any guard is set.
+    ///
+    /// If `guard` returns an error, retrying stops immediately with
+    /// [RetryPolicy::Fail](crate::RetryPolicy::Fail) containing it,
+    /// without counting it as an attempt.
+    pub fn before_attempt(mut self, guard: impl FnMut() -> Result<(), E> + Send + 'static) -> Self {
+        self.core.before_attempt = Some(Box::new(guard));
+        self
+    }
+
+    /// Classifies errors returned early by using `?` before deciding whether to retry them,
+    /// so that the logic of which errors are fatal lives outside of the factory.
+    ///
+    /// If `classifier` returns [RetryPolicy::Fail](crate::RetryPolicy::Fail),
+    /// retrying stops with it. Otherwise the original error is retried as usual.
+    pub fn classifier(
+        mut self,
+        classifier: impl Fn(&anyhow::Error) -> RetryPolicy<E> + Send + 'static,
+    ) -> Self {
+        self.core.classifier = Some(Box::new(classifier));
+        self
+    }
+
+    /// Asks `retry_strategy` for the delay instead of the main one when an error `matches`,
+    /// e.g. to back off longer on rate limiting than on server errors.
+    ///
+    /// Strategies are tried in the order they were added. They are only asked for delays,
+    /// whether early returned errors are retried and outcomes are decided by the main strategy.
+    pub fn with_strategy_for(
+        mut self,
+        matches: impl Fn(&RetryPolicy<E>) -> bool + Send + 'static,
+        retry_strategy: impl RetryStrategy + Send + 'static,
+    ) -> Self {
+        self.core.routes.push((Box::new(matches), Box::new(retry_strategy)));
+        self
+    }
+
+    /// Gives up with [TooManyAttempts](StopReason::TooManyAttempts) once more than `max`
+    /// errors `match` since the last success, even if the strategy would retry, e.g. to retry
+    /// timeouts many times but errors of a misconfigured client only once.
+    ///
+    /// Matching errors do not have to be consecutive: every budget counts all of its
+    /// errors separately, and is reset only after a success.
+    pub fn max_attempts_for(
+        mut self,
+        matches: impl Fn(&RetryPolicy<E>) -> bool + Send + 'static,
+        max: usize,
+    ) -> Self {
+        self.core.budgets.push(ErrorBudget { matches: Box::new(matches), max, spent: 0 });
+        self
+    }
+
+    /// Hedges requests: if an attempt has not finished in `delay`, another one
+    /// is started concurrently, without cancelling the previous, up to `max_in_flight`
+    /// attempts at once. The first successful attempt wins.
+    ///
+    /// A failed attempt is handled by the [retry strategy](crate::retry_strategy::RetryStrategy)
+    /// as usual, but its delay is applied only when no other attempts are in flight.
+    pub fn hedged(mut self, delay: Duration, max_in_flight: usize) -> Self {
+        self.core.hedging = Some(Hedging { delay, max_in_flight: max_in_flight.max(1) });
+        self
+    }
+}
+
+impl<F, Fut, RS, E, C: Clock> RetryFuture<F, Fut, RS, E, C>
+where
+    E: Clone + Send + 'static,
+{
+    /// Sends every error to `sender` as soon as it is returned from the inner future.
+    ///
+    /// Sending never blocks the retry loop: if the channel is full or the receiver
+    /// is dropped, the error is not sent and retrying continues.
+    ///
+    /// Since `anyhow::Error` is not `Clone`, errors inside `Retry` variant
+    /// are sent with their message only.
+    pub fn error_sink(mut self, sender: Sender<RetryPolicy<E>>) -> Self {
+        self.core.error_hooks.push(Box::new(move |err: &RetryPolicy<E>| {
+            let _ = sender.try_send(err.duplicate());
+        }));
+        self
+    }
+
+    /// Pushes a copy of every error to `errors` as soon as it is returned from
+    /// the inner future, so that they can be inspected even if the future is dropped,
+    /// e.g. cancelled by a timeout.
+    ///
+    /// Like in [error_sink](RetryFuture::error_sink), errors inside `Retry` variant
+    /// are copied with their message only.
+    pub fn share_errors(mut self, errors: Arc<std::sync::Mutex<Vec<RetryPolicy<E>>>>) -> Self {
+        self.core.error_hooks.push(Box::new(move |err: &RetryPolicy<E>| {
+            let mut errors = errors.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            errors.push(err.duplicate());
+        }));
+        self
+    }
+
+    /// Returns a [RetryHandle] which takes errors accumulated so far while
+    /// the future is running, see [share_errors](RetryFuture::share_errors)
+    pub fn with_handle(self) -> (Self, RetryHandle<E>) {
+        let handle = RetryHandle { errors: Default::default() };
+        (self.share_errors(handle.errors.clone()), handle)
+    }
+}
+
+/// Type-erased [RetryFuture], e.g. to return it from a function
+/// without leaking the type of the factory, see [RetryFuture::boxed]
+pub type BoxRetryFuture<T, E> = Pin<Box<dyn Future<Output = Result<T, RetryError<E>>>>>;
+
+impl<F, Fut, RS, E, C: Clock> RetryFuture<F, Fut, RS, E, C>
+where
+    Self: Future<Output = Result<Fut::Ok, RetryError<E>>> + 'static,
+    Fut: TryFuture,
+{
+    /// Boxes the future, erasing the types of the factory, the strategy and the clock
+    pub fn boxed(self) -> BoxRetryFuture<Fut::Ok, E> {
+        Box::pin(self)
+    }
+}
+
+impl<F, Fut, RS, E, C: Clock> RetryFuture<F, Fut, RS, E, C>
+where
+    Self: Future<Output = Result<Fut::Ok, RetryError<E>>>,
+    Fut: TryFuture,
+{
+    /// Converts [RetryError] into a custom error when retrying is given up
+    pub fn map_err<G, E2>(self, f: G) -> MapErr<Self, G>
+    where
+        G: FnOnce(RetryError<E>) -> E2,
+    {
+        TryFutureExt::map_err(self, f)
+    }
+
+    /// Keeps starting new attempts after successful ones until there are `k` successes,
+    /// e.g. to warm up a service or to probe its health.
+    ///
+    /// Failed attempts are retried as usual and `attempts_before` starts from zero
+    /// after every success, so the [retry strategy](RetryStrategy) limits failures in a row.
+    /// Giving up on [Fail](RetryPolicy::Fail) or exhaustion of the strategy drops
+    /// the collected results.
+    pub fn until_successes(self, k: usize) -> UntilSuccesses<Self, Fut::Ok> {
+        UntilSuccesses { future: self, successes: Vec::with_capacity(k), k }
+    }
+
+    /// Yields the outcome of every attempt as soon as it is known instead of
+    /// only the final result. The stream ends after a success, a
+    /// [Fail](RetryPolicy::Fail) or when the strategy gives up.
+    ///
+    /// Errors are yielded as copies, like in [error_sink](RetryFuture::error_sink),
+    /// while the originals are kept for the checks which compare errors, e.g.
+    /// [stall_detector](RetryFuture::stall_detector). [RetryError] with its
+    /// [reason](RetryError::reason) is not available.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use futures::StreamExt;
+    /// use retry_future::{LinearRetryStrategy, RetryFuture, RetryPolicy};
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let mut attempt = 0;
+    /// let attempts: Vec<_> = RetryFuture::new(
+    ///     move || {
+    ///         attempt += 1;
+    ///         let result = if attempt < 3 { Err(RetryPolicy::<()>::Retry(None)) } else { Ok(attempt) };
+    ///         async move { result }
+    ///     },
+    ///     LinearRetryStrategy::new(),
+    /// )
+    /// .into_attempt_stream()
+    /// .collect()
+    /// .await;
+    /// assert_eq!(3, attempts.len());
+    /// # }
+    /// ```
+    pub fn into_attempt_stream(self) -> AttemptStream<Self, Fut::Ok, E>
+    where
+        E: Clone,
+    {
+        AttemptStream { future: self, outcomes: VecDeque::new(), yielded: 0, done: false }
+    }
+}
+
+/// Stream returned by [RetryFuture::into_attempt_stream]
+#[pin_project]
+pub struct AttemptStream<R, T, E> {
+    #[pin]
+    future: R,
+    /// Outcomes which are known but not yielded yet
+    outcomes: VecDeque<Result<T, RetryPolicy<E>>>,
+    /// Number of errors of the future which are already in `outcomes`
+    yielded: usize,
+    done: bool,
+}
+
+impl<F, Fut, RS, E, C> Stream for AttemptStream<RetryFuture<F, Fut, RS, E, C>, Fut::Ok, E>
+where
+    C: Clock,
+    F: FutureFactory<Future = Fut>,
+    Fut: TryFuture<Error = RetryPolicy<E>>,
+    E: Clone + Debug,
+    RS: RetryStrategy,
+{
+    type Item = Result<Fut::Ok, RetryPolicy<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if let Some(outcome) = this.outcomes.pop_front() {
+                return Poll::Ready(Some(outcome));
+            }
+            if *this.done {
+                return Poll::Ready(None);
+            }
+            let poll = this.future.as_mut().poll(cx);
+            // errors are taken out of the future when it gives up
+            let errors = &this.future.as_mut().project().core.errors;
+            if let Some(new) = errors.get(*this.yielded..) {
+                this.outcomes.extend(new.iter().map(|err| Err(err.duplicate())));
+                *this.yielded = errors.len();
+            }
+            match poll {
+                Poll::Ready(result) => {
+                    *this.done = true;
+                    match result {
+                        Ok(t) => this.outcomes.push_back(Ok(t)),
+                        Err(error) => {
+                            let errors = error.into_errors().into_iter().skip(*this.yielded);
+                            this.outcomes.extend(errors.map(Err))
+                        }
+                    }
+                }
+                Poll::Pending if this.outcomes.is_empty() => return Poll::Pending,
+                Poll::Pending => {}
+            }
+        }
+    }
+}
+
+/// Future returned by [RetryFuture::until_successes]
+#[pin_project]
+pub struct UntilSuccesses<R, T> {
+    #[pin]
+    future: R,
+    successes: Vec<T>,
+    k: usize,
+}
+
+impl<R, T, E> Future for UntilSuccesses<R, T>
+where
+    R: Future<Output = Result<T, RetryError<E>>>,
+{
+    type Output = Result<Vec<T>, RetryError<E>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut this = self.project();
+        while this.successes.len() < *this.k {
+            // RetryFuture starts over once polled after a success
+            let t = ready!(this.future.as_mut().poll(cx))?;
+            this.successes.push(t);
+        }
+        Poll::Ready(Ok(std::mem::take(this.successes)))
+    }
+}
+
+impl<F, Fut, RS, E, C> Future for RetryFuture<F, Fut, RS, E, C>
+where
+    C: Clock,
+    F: FutureFactory<Future = Fut>,
     Fut: TryFuture<Error = RetryPolicy<E>>,
     E: Debug,
     RS: RetryStrategy,
@@ -66,59 +1141,594 @@ where
     type Output = Result<Fut::Ok, RetryError<E>>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let poll = self.as_mut().poll_attempts(cx);
+        let this = self.project();
+        if let Some(on_cancel) = &mut this.core.on_cancel {
+            on_cancel.armed = poll.is_pending()
+                && matches!(this.state.as_ref().get_ref(), FutureState::TimerActive { .. });
+        }
+        poll
+    }
+}
+
+impl<F, Fut, RS, E, C> RetryFuture<F, Fut, RS, E, C>
+where
+    C: Clock,
+    F: FutureFactory<Future = Fut>,
+    Fut: TryFuture<Error = RetryPolicy<E>>,
+    E: Debug,
+    RS: RetryStrategy,
+{
+    fn poll_attempts(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Result<Fut::Ok, RetryError<E>>> {
+        let core = self.as_mut().project().core;
+        if let Some(until) = &mut core.until {
+            if until.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(core.give_up(StopReason::DeadlineExceeded)));
+            }
+        }
         loop {
-            let retry_future = self.as_mut().project();
-            let new_state = match retry_future.state.project() {
-                FutureStateProj::WaitingForFuture { future } => match ready!(future.try_poll(cx)) {
-                    Ok(t) => {
-                        *retry_future.attempts_before = 0;
-                        return Poll::Ready(Ok(t));
-                    }
-                    Err(err) => {
-                        #[cfg(feature = "log")]
-                        log::trace!("Error returned from future - {err:?}");
-                        retry_future.errors.push(err);
-                        let err = retry_future.errors.last().unwrap(); // cannot panic as we just pushed to vec
-                        let new_state = match err {
-                            RetryPolicy::Retry(maybe_err) => {
-                                if matches!(maybe_err, Some(e) if e.is_early_returned)
-                                    && !retry_future.retry_strategy.retry_early_returned_errors()
-                                {
-                                    return Poll::Ready(Err(RetryError {
-                                        errors: std::mem::take(retry_future.errors),
-                                    }));
+            let mut this = self.as_mut().project();
+            let core = this.core;
+            let new_state = match this.state.as_mut().project() {
+                FutureStateProj::WaitingForFuture { future, timeout } => {
+                    let poll = {
+                        #[cfg(feature = "tracing")]
+                        let _entered = core.attempt_span.as_ref().map(tracing::Span::enter);
+                        future.try_poll(cx)
+                    };
+                    let result = match poll {
+                        Poll::Ready(result) => result,
+                        Poll::Pending => match timeout.as_pin_mut() {
+                            Some(timeout) => {
+                                ready!(timeout.poll(cx));
+                                Err(core.timed_out())
+                            }
+                            None => return Poll::Pending,
+                        },
+                    };
+                    core.permit = None;
+                    match result {
+                        Ok(t) => {
+                            this.state.set(FutureState::Start);
+                            if core.continues_streak() {
+                                cx.waker().wake_by_ref();
+                                return Poll::Pending;
+                            }
+                            core.on_success();
+                            return Poll::Ready(Ok(t));
+                        }
+                        Err(err) => match core.on_error(err, this.clock.now()) {
+                            Ok(delay) if core.cleans_up() => core.clean_up(delay),
+                            // yield to the executor instead of spinning through attempts
+                            Ok(Duration::ZERO) => {
+                                this.state.set(FutureState::Start);
+                                cx.waker().wake_by_ref();
+                                return Poll::Pending;
+                            }
+                            Ok(duration) => {
+                                FutureState::TimerActive { delay: this.clock.sleep(duration) }
+                            }
+                            Err(reason) => return Poll::Ready(Err(core.give_up(reason))),
+                        },
+                    }
+                }
+                FutureStateProj::Start => {
+                    if core.is_shutdown() {
+                        return Poll::Ready(Err(core.give_up(StopReason::Cancelled)));
+                    }
+                    if let Some(pending) = core.deciding.take() {
+                        this.state.set(FutureState::Deciding { pending });
+                        continue;
+                    }
+                    if let Some(delay) = core.delay_before_first_attempt.take() {
+                        this.state.set(FutureState::TimerActive { delay: this.clock.sleep(delay) });
+                        continue;
+                    }
+                    if let (Some(semaphore), None, None) =
+                        (&core.semaphore, &core.permit, core.hedging)
+                    {
+                        let acquire = Box::pin(semaphore.clone().acquire_owned());
+                        this.state.set(FutureState::Acquiring { acquire });
+                        continue;
+                    }
+                    let created = this.first.take();
+                    if let Err(reason) = core.before_attempt(created.is_none()) {
+                        return Poll::Ready(Err(core.give_up(reason)));
+                    }
+                    #[cfg(feature = "tracing")]
+                    core.start_attempt_span();
+                    let future = match created.map_or_else(|| core.new_future(this.factory), Ok) {
+                        Ok(future) => future,
+                        Err(err) => {
+                            core.permit = None;
+                            match core.on_error(err, this.clock.now()) {
+                                Ok(delay) if core.cleans_up() => {
+                                    this.state.set(core.clean_up(delay));
+                                    continue;
                                 }
-                                let check_attempt_result = retry_future
-                                    .retry_strategy
-                                    .check_attempt(*retry_future.attempts_before);
-                                match check_attempt_result {
-                                    Ok(duration) => {
-                                        FutureState::TimerActive { delay: sleep(duration) }
-                                    }
-                                    Err(_) => {
-                                        return Poll::Ready(Err(RetryError {
-                                            errors: std::mem::take(retry_future.errors),
-                                        }));
-                                    }
+                                Ok(Duration::ZERO) => {
+                                    cx.waker().wake_by_ref();
+                                    return Poll::Pending;
                                 }
+                                Ok(duration) => {
+                                    let delay = this.clock.sleep(duration);
+                                    this.state.set(FutureState::TimerActive { delay });
+                                    continue;
+                                }
+                                Err(reason) => return Poll::Ready(Err(core.give_up(reason))),
                             }
-                            RetryPolicy::Fail(_) => {
-                                return Poll::Ready(Err(RetryError {
-                                    errors: std::mem::take(retry_future.errors),
-                                }));
-                            }
-                        };
-                        *retry_future.attempts_before += 1;
-                        new_state
+                        }
+                    };
+                    match core.hedging {
+                        Some(_) => FutureState::Hedging {
+                            in_flight: std::iter::once(future.into_future()).collect(),
+                            hedge: None,
+                        },
+                        None => {
+                            let timeout =
+                                core.plan_timeout().map(|timeout| this.clock.sleep(timeout));
+                            FutureState::WaitingForFuture { future, timeout }
+                        }
                     }
+                }
+                FutureStateProj::Acquiring { acquire } => match ready!(acquire.as_mut().poll(cx)) {
+                    Ok(permit) => {
+                        core.permit = Some(permit);
+                        FutureState::Start
+                    }
+                    Err(_) => return Poll::Ready(Err(core.give_up(StopReason::Cancelled))),
                 },
+                FutureStateProj::Deciding { pending } => {
+                    let decision = ready!(pending.decision.as_mut().poll(cx));
+                    match core.on_decision(decision, pending.hint, pending.summary.take()) {
+                        Ok(delay) if core.cleans_up() => core.clean_up(delay),
+                        Ok(Duration::ZERO) => FutureState::Start,
+                        Ok(duration) => {
+                            FutureState::TimerActive { delay: this.clock.sleep(duration) }
+                        }
+                        Err(reason) => return Poll::Ready(Err(core.give_up(reason))),
+                    }
+                }
+                FutureStateProj::CleaningUp { cleanup, delay } => {
+                    ready!(cleanup.as_mut().poll(cx));
+                    match *delay {
+                        Duration::ZERO => FutureState::Start,
+                        delay => FutureState::TimerActive { delay: this.clock.sleep(delay) },
+                    }
+                }
                 FutureStateProj::TimerActive { delay } => {
                     ready!(delay.poll(cx));
-                    FutureState::WaitingForFuture { future: (retry_future.factory)() }
+                    if core.is_shutdown() {
+                        return Poll::Ready(Err(core.give_up(StopReason::Cancelled)));
+                    }
+                    if core.yield_between_attempts {
+                        this.state.set(FutureState::Start);
+                        cx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    }
+                    FutureState::Start
+                }
+                FutureStateProj::Hedging { in_flight, mut hedge } => {
+                    let hedging = core.hedging.expect("hedging state requires hedging config");
+                    if let Err(reason) = ready!(core.poll_deciding(cx)) {
+                        return Poll::Ready(Err(core.give_up(reason)));
+                    }
+                    loop {
+                        match hedge.as_mut().as_pin_mut() {
+                            Some(timer) => {
+                                if timer.poll(cx).is_pending() {
+                                    break;
+                                }
+                                hedge.set(None);
+                                if core.is_shutdown() {
+                                    return Poll::Ready(Err(core.give_up(StopReason::Cancelled)));
+                                }
+                                if let Err(reason) = core.before_attempt(true) {
+                                    return Poll::Ready(Err(core.give_up(reason)));
+                                }
+                                match core.new_future(this.factory) {
+                                    Ok(future) => in_flight.push(future.into_future()),
+                                    // other attempts are still in flight
+                                    Err(err) => {
+                                        if let Err(reason) = core.on_error(err, this.clock.now()) {
+                                            return Poll::Ready(Err(core.give_up(reason)));
+                                        }
+                                        if let Err(reason) = ready!(core.poll_deciding(cx)) {
+                                            return Poll::Ready(Err(core.give_up(reason)));
+                                        }
+                                    }
+                                }
+                            }
+                            None if in_flight.len() < hedging.max_in_flight => {
+                                hedge.set(Some(this.clock.sleep(hedging.delay)))
+                            }
+                            None => break,
+                        }
+                    }
+                    let poll = {
+                        #[cfg(feature = "tracing")]
+                        let _entered = core.attempt_span.as_ref().map(tracing::Span::enter);
+                        in_flight.poll_next_unpin(cx)
+                    };
+                    match ready!(poll) {
+                        Some(Ok(t)) => {
+                            this.state.set(FutureState::Start);
+                            if core.continues_streak() {
+                                cx.waker().wake_by_ref();
+                                return Poll::Pending;
+                            }
+                            core.on_success();
+                            return Poll::Ready(Ok(t));
+                        }
+                        Some(Err(err)) => match core.on_error(err, this.clock.now()) {
+                            Ok(_) if !in_flight.is_empty() => continue,
+                            Ok(delay) if core.cleans_up() => core.clean_up(delay),
+                            Ok(Duration::ZERO) => {
+                                this.state.set(FutureState::Start);
+                                cx.waker().wake_by_ref();
+                                return Poll::Pending;
+                            }
+                            Ok(duration) => {
+                                FutureState::TimerActive { delay: this.clock.sleep(duration) }
+                            }
+                            Err(reason) => return Poll::Ready(Err(core.give_up(reason))),
+                        },
+                        None => unreachable!("there is always at least one attempt in flight"),
+                    }
+                }
+            };
+
+            this.state.set(new_state);
+        }
+    }
+}
+
+impl<RS: RetryStrategy, E: Debug> Core<RS, E> {
+    /// Whether a retry decided by [on_error](Core::on_error) waits for
+    /// [RetryFuture::before_retry] first, which is not the case while the decision
+    /// of the strategy is still pending
+    fn cleans_up(&self) -> bool {
+        self.before_retry.is_some() && self.deciding.is_none()
+    }
+
+    fn clean_up<Fut, S>(&mut self, delay: Duration) -> FutureState<Fut, S> {
+        let before_retry = self.before_retry.as_mut().expect("checked by cleans_up");
+        FutureState::CleaningUp { cleanup: before_retry(), delay }
+    }
+
+    /// Counts a success towards [RetryFuture::require_streak],
+    /// returning `true` if another attempt is needed
+    fn continues_streak(&mut self) -> bool {
+        self.stalls = None;
+        self.streak += 1;
+        if self.streak >= self.required_streak {
+            self.streak = 0;
+            return false;
+        }
+        self.retry_strategy.record_outcome(true);
+        true
+    }
+
+    fn on_success(&mut self) {
+        self.retry_strategy.record_outcome(true);
+        self.publish(RetryEvent::Succeeded { attempt: self.attempts_before });
+        #[cfg(feature = "tracing")]
+        self.finish_spans("success", self.attempts_before + 1);
+        if let Some(on_success) = self.on_success.take() {
+            on_success(self.attempts_before);
+        }
+        self.budgets.iter_mut().for_each(|budget| budget.spent = 0);
+        self.recent_errors.clear();
+        self.attempts_before = 0;
+    }
+
+    /// Peeks the delay which would follow the attempt to use it as the attempt timeout,
+    /// see [RetryFuture::attempt_timeout_from_strategy]
+    fn plan_timeout(&mut self) -> Option<Duration> {
+        self.attempt_timeout = self
+            .attempt_timeout_from_strategy
+            .then(|| self.retry_strategy.peek_next_delay(self.attempts_before))
+            .flatten();
+        self.attempt_timeout
+    }
+
+    /// Calls the factory, catching its panic if [RetryFuture::catch_factory_panics] is set
+    fn new_future<F: FutureFactory>(&self, factory: &mut F) -> Result<F::Future, RetryPolicy<E>> {
+        if !self.catch_factory_panics {
+            return Ok(factory.new_future(self.attempts_before));
+        }
+        let attempts_before = self.attempts_before;
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            factory.new_future(attempts_before)
+        }))
+        .map_err(|panic| {
+            let message = panic
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("Box<dyn Any>");
+            RetryPolicy::Retry(Some(Error::msg(format!("factory panicked: {message}"))))
+        })
+    }
+
+    fn timed_out(&self) -> RetryPolicy<E> {
+        let timeout = self.attempt_timeout.unwrap_or_default();
+        RetryPolicy::Retry(Some(Error::msg(format!("attempt timed out after {timeout:?}"))))
+    }
+
+    /// Whether `err` is the same as the previous errors, see [RetryFuture::stop_on_repeated_errors]
+    fn is_repeated(&mut self, err: &RetryPolicy<E>) -> bool {
+        let Some(n) = self.stop_on_repeated_errors else {
+            return false;
+        };
+        if matches!(err, RetryPolicy::Retry(Some(Error { retried: Some(Retried::Ok(_)), .. }))) {
+            self.recent_errors.clear();
+            return false;
+        }
+        if self.recent_errors.len() == n {
+            self.recent_errors.pop_front();
+        }
+        self.recent_errors.push_back(err.summary());
+        self.recent_errors.len() == n
+            && self.recent_errors.iter().all(|summary| *summary == self.recent_errors[0])
+    }
+
+    /// Whether `err` made no progress since the previous error too many times,
+    /// see [RetryFuture::stall_detector]
+    fn is_stalled(&mut self, err: &mut RetryPolicy<E>) -> bool {
+        let Some((n, no_progress)) = &self.stall_detector else {
+            return false;
+        };
+        let stalled = match (self.errors.last_mut(), err) {
+            (Some(RetryPolicy::Retry(Some(previous))), RetryPolicy::Retry(Some(next))) => {
+                self.stalls.is_some() && no_progress(previous, next)
+            }
+            _ => false,
+        };
+        let stalls = match self.stalls {
+            Some(stalls) if stalled => stalls + 1,
+            _ => 0,
+        };
+        self.stalls = Some(stalls);
+        stalls >= *n && *n > 0
+    }
+
+    /// Returns either a delay before the next attempt or why retrying must stop
+    fn on_error(&mut self, err: RetryPolicy<E>, now: Instant) -> Result<Duration, StopReason> {
+        #[cfg(feature = "log")]
+        log::trace!(
+            "Error returned from future - {err:?}, retry strategy - {}",
+            self.retry_strategy.name()
+        );
+        self.retry_strategy.record_outcome(false);
+        self.streak = 0;
+        if let Some(quiet) = self.reset_backoff_after {
+            if self.last_failure.is_some_and(|last| now.duration_since(last) > quiet) {
+                self.attempts_before = 0;
+            }
+            self.last_failure = Some(now);
+        }
+        let err = match (err, &self.classifier) {
+            (RetryPolicy::Retry(Some(e)), Some(classifier)) if e.is_early_returned => {
+                match classifier(&e.error) {
+                    RetryPolicy::Fail(fail) => RetryPolicy::Fail(fail),
+                    RetryPolicy::Retry(_) => RetryPolicy::Retry(Some(e)),
+                }
+            }
+            (err, _) => err,
+        };
+        let mut err = match err {
+            RetryPolicy::Retry(Some(mut e)) if self.compact_errors => {
+                let retried = e.retried.take();
+                RetryPolicy::Retry(Some(Error { retried, ..e.duplicate() }))
+            }
+            err => err,
+        };
+        let repeated = self.is_repeated(&err);
+        let stalled = self.is_stalled(&mut err);
+        self.errors.push(err);
+        let err = self.errors.last().unwrap(); // cannot panic as we just pushed to vec
+        for error_hook in &mut self.error_hooks {
+            error_hook(err);
+        }
+        let summary = (self.timeline.is_some() || self.event_bus.is_some()).then(|| err.summary());
+        let decision = match err {
+            RetryPolicy::Retry(maybe_err) => {
+                let early_returned = matches!(maybe_err, Some(e) if e.is_early_returned);
+                if early_returned {
+                    self.early_returned += 1;
+                }
+                if early_returned
+                    && (!self.retry_strategy.retry_early_returned_errors()
+                        || self
+                            .max_early_return_retries
+                            .is_some_and(|max| self.early_returned > max))
+                {
+                    Err(StopReason::EarlyReturned)
+                } else if matches!(maybe_err, Some(e) if e.forced == Some(Decision::Stop)) {
+                    Err(StopReason::Inspected)
+                } else if self.is_shutdown() {
+                    Err(StopReason::Cancelled)
+                } else if repeated {
+                    Err(StopReason::RepeatedErrors)
+                } else if stalled {
+                    Err(StopReason::Stalled)
+                } else if self.budgets.iter_mut().fold(false, |over, b| over | b.spend(err)) {
+                    Err(StopReason::TooManyAttempts)
+                } else {
+                    let route = self.routes.iter_mut().find(|(matches, _)| matches(err));
+                    let strategy: &mut dyn RetryStrategy = match route {
+                        Some((_, strategy)) => strategy.as_mut(),
+                        None => &mut self.retry_strategy,
+                    };
+                    if let Some(decision) = strategy.check_attempt_async(self.attempts_before) {
+                        // awaited from the start state, see `on_decision`
+                        self.deciding = Some(PendingDecision {
+                            decision,
+                            hint: DelayHint::of(maybe_err.as_ref()),
+                            summary,
+                        });
+                        return Ok(Duration::ZERO);
+                    }
+                    strategy
+                        .check_attempt(self.attempts_before)
+                        .map(|delay| DelayHint::of(maybe_err.as_ref()).apply(delay))
+                        .map_err(StopReason::from)
                 }
+            }
+            RetryPolicy::Fail(_) => Err(StopReason::Fail),
+        };
+        self.record_decision(decision, summary)
+    }
+
+    /// Awaits the pending decision of the strategy while hedged attempts are still
+    /// in flight, so that it is not replaced by the decision for the next error.
+    /// Its delay is not needed, as the next attempt is already running.
+    fn poll_deciding(&mut self, cx: &mut Context) -> Poll<Result<(), StopReason>> {
+        let Some(pending) = &mut self.deciding else {
+            return Poll::Ready(Ok(()));
+        };
+        let decision = ready!(pending.decision.as_mut().poll(cx));
+        let PendingDecision { hint, summary, .. } = self.deciding.take().expect("checked above");
+        Poll::Ready(self.on_decision(decision, hint, summary).map(|_| ()))
+    }
+
+    /// Completes [on_error](Core::on_error) with the awaited decision of the strategy
+    fn on_decision(
+        &mut self,
+        decision: Result<Duration, RetryStop>,
+        hint: DelayHint,
+        summary: Option<String>,
+    ) -> Result<Duration, StopReason> {
+        let decision = decision.map(|delay| hint.apply(delay));
+        self.record_decision(decision.map_err(StopReason::from), summary)
+    }
+
+    /// `summary` of the error is recorded into the [timeline](RetryFuture::record_timeline)
+    /// and published to the [event bus](RetryFuture::with_event_bus)
+    fn record_decision(
+        &mut self,
+        decision: Result<Duration, StopReason>,
+        summary: Option<String>,
+    ) -> Result<Duration, StopReason> {
+        if let Some(error) = summary {
+            let record =
+                AttemptRecord { attempt: self.attempts_before, delay: decision.ok(), error };
+            if let Some(timeline) = &self.timeline {
+                timeline.push(record.clone());
+            }
+            self.publish(RetryEvent::AttemptFailed {
+                attempt: record.attempt,
+                error: record.error,
+                delay: record.delay,
+            });
+        }
+        #[cfg(feature = "tracing")]
+        if let Some(attempt_span) = self.attempt_span.take() {
+            attempt_span.record("outcome", "error");
+            if let Ok(delay) = decision {
+                attempt_span.record("delay", tracing::field::debug(delay));
+            }
+        }
+        if decision.is_ok() {
+            self.attempts_before += 1;
+        }
+        decision
+    }
+}
+
+impl<RS, E: Debug> Core<RS, E> {
+    /// Runs the [guard](RetryFuture::before_attempt), pushing its error as `Fail`
+    /// The guard is skipped for the first attempt, which was already created by `new`.
+    fn before_attempt(&mut self, guarded: bool) -> Result<(), StopReason> {
+        if let (true, Some(before_attempt)) = (guarded, &mut self.before_attempt) {
+            if let Err(e) = before_attempt() {
+                self.errors.push(RetryPolicy::Fail(e));
+                return Err(StopReason::Fail);
+            }
+        }
+        self.publish(RetryEvent::AttemptStarted { attempt: self.attempts_before });
+        Ok(())
+    }
+
+    fn give_up(&mut self, reason: StopReason) -> RetryError<E> {
+        let pending = matches!(
+            self.errors.last(),
+            Some(RetryPolicy::Retry(Some(Error { retried: Some(Retried::Ok(_)), .. })))
+        );
+        let reason = match reason {
+            StopReason::TooManyAttempts if pending => StopReason::ExhaustedWhilePending,
+            reason => reason,
+        };
+        self.publish(RetryEvent::GaveUp { reason });
+        #[cfg(any(feature = "log", feature = "tracing"))]
+        {
+            let attempts = self.errors.len();
+            let last_error = self.errors.last().map(RetryPolicy::summary).unwrap_or_default();
+            #[cfg(feature = "log")]
+            log::warn!("Gave up after {attempts} attempts - {reason:?}, last error - {last_error}");
+            #[cfg(feature = "tracing")]
+            tracing::warn!(attempts, ?reason, last_error, "Gave up retrying");
+        }
+        #[cfg(feature = "tracing")]
+        self.finish_spans(&format!("{reason:?}"), self.errors.len());
+        if self.annotate_exhaustion {
+            let message = match reason {
+                StopReason::TooManyAttempts | StopReason::ExhaustedWhilePending => {
+                    Some("exceeded max attempts")
+                }
+                StopReason::Deadline => Some("exceeded deadline of retry strategy"),
+                StopReason::Budget => Some("exceeded retry budget"),
+                _ => None,
             };
+            if let Some(message) = message {
+                self.errors.push(RetryPolicy::Retry(Some(Error::msg(message))));
+            }
+        }
+        let mut error = RetryError::new(std::mem::take(&mut self.errors), reason);
+        if let Some(timeline) = &self.timeline {
+            error.timeline = timeline.records();
+        }
+        error
+    }
+
+    #[cfg(feature = "tracing")]
+    fn start_attempt_span(&mut self) {
+        if let Some(span) = &self.span {
+            self.attempt_span = Some(tracing::info_span!(
+                parent: span,
+                "retry_attempt",
+                attempt = self.attempts_before + 1,
+                delay = tracing::field::Empty,
+                outcome = tracing::field::Empty,
+            ));
+        }
+    }
+
+    /// Records the final `outcome` on the span of [RetryFuture::in_span]
+    #[cfg(feature = "tracing")]
+    fn finish_spans(&mut self, outcome: &str, attempts: usize) {
+        if let Some(attempt_span) = self.attempt_span.take() {
+            attempt_span.record("outcome", outcome);
+        }
+        if let Some(span) = &self.span {
+            span.record("retry.outcome", outcome);
+            span.record("retry.attempts", attempts);
+        }
+    }
 
-            self.as_mut().project().state.set(new_state);
+    /// See [RetryFuture::with_event_bus]
+    fn publish(&self, event: RetryEvent) {
+        if let Some(event_bus) = &self.event_bus {
+            // no receivers is not an error for the retry loop
+            let _ = event_bus.send(event);
         }
     }
+
+    fn is_shutdown(&self) -> bool {
+        matches!(&self.shutdown, Some(shutdown) if shutdown.load(Ordering::Relaxed))
+    }
 }
@@ -0,0 +1,45 @@
+use crate::RetryPolicy;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Companion of a [RetryFuture](crate::RetryFuture) taking errors accumulated so far
+/// while it is still running, e.g. to show progress.
+///
+/// Created by [RetryFuture::with_handle](crate::RetryFuture::with_handle).
+/// Like in [share_errors](crate::RetryFuture::share_errors), errors inside `Retry`
+/// variant are copied with their message only, and the
+/// [RetryError](crate::RetryError) returned at the end still contains all of them.
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::{LinearRetryStrategy, RetryFuture, RetryPolicy};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let (future, handle) = RetryFuture::new(
+///     || async { Err::<(), _>(RetryPolicy::<()>::Retry(None)) },
+///     LinearRetryStrategy::new().max_attempts(2),
+/// )
+/// .with_handle();
+/// let _ = future.await;
+/// assert_eq!(3, handle.drain_errors().len());
+/// assert!(handle.drain_errors().is_empty());
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RetryHandle<E> {
+    pub(crate) errors: Arc<Mutex<Vec<RetryPolicy<E>>>>,
+}
+
+impl<E> Clone for RetryHandle<E> {
+    fn clone(&self) -> Self {
+        Self { errors: self.errors.clone() }
+    }
+}
+
+impl<E> RetryHandle<E> {
+    /// Removes and returns errors returned since the previous call
+    pub fn drain_errors(&self) -> Vec<RetryPolicy<E>> {
+        std::mem::take(&mut *self.errors.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
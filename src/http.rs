@@ -0,0 +1,366 @@
+use crate::error::{Error, RetryError};
+use crate::future::RetryFuture;
+use crate::retry_strategy::RetryStrategy;
+use crate::RetryPolicy;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use std::ops::RangeInclusive;
+
+/// Classifies reqwest error by its kind.
+///
+/// Failures to connect (including DNS resolution), timeouts and errors while sending
+/// the request are retried, as well as `5xx` and `429 Too Many Requests` statuses
+/// returned by [error_for_status](reqwest::Response::error_for_status).
+/// Others, e.g. invalid URLs, `4xx` statuses or failures to decode the body, are unrecoverable.
+///
+/// Note that reqwest reports TLS handshake failures as connect errors, so they are retried too.
+pub fn classify_reqwest_error(err: &reqwest::Error) -> RetryPolicy<()> {
+    let retry = if let Some(status) = err.status() {
+        RetryableStatuses::default().contains(status)
+    } else {
+        err.is_connect() || err.is_timeout() || err.is_request()
+    };
+    if retry {
+        RetryPolicy::Retry(Some(Error::msg(err.to_string())))
+    } else {
+        RetryPolicy::Fail(())
+    }
+}
+
+/// Retries reqwest requests classified by [classify_reqwest_error]
+pub trait RetryRequestExt {
+    /// Sends the request until it succeeds or `retry_strategy` gives up.
+    ///
+    /// Only idempotent methods (`GET`, `HEAD`, `OPTIONS`, `TRACE`, `PUT`, `DELETE`)
+    /// are retried, other requests fail on the first error unless
+    /// [retry_non_idempotent](RetryRequest::retry_non_idempotent) is set.
+    /// Requests with a streaming body cannot be sent twice, so they are never retried.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust,no_run
+    /// use retry_future::{LinearRetryStrategy, RetryRequestExt};
+    ///
+    /// # async fn send() -> anyhow::Result<()> {
+    /// let client = reqwest::Client::new();
+    /// let response = client
+    ///     .post("http://localhost/orders")
+    ///     .header("idempotency-key", "42")
+    ///     .retry(LinearRetryStrategy::new())
+    ///     .retry_non_idempotent()
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn retry<RS: RetryStrategy>(self, retry_strategy: RS) -> RetryRequest<RS>;
+}
+
+impl RetryRequestExt for RequestBuilder {
+    fn retry<RS: RetryStrategy>(self, retry_strategy: RS) -> RetryRequest<RS> {
+        RetryRequest {
+            builder: self,
+            retry_strategy,
+            retry_non_idempotent: false,
+            retryable_statuses: RetryableStatuses::default(),
+        }
+    }
+}
+
+/// Request created by [retry](RetryRequestExt::retry)
+#[derive(Debug)]
+pub struct RetryRequest<RS> {
+    builder: RequestBuilder,
+    retry_strategy: RS,
+    retry_non_idempotent: bool,
+    retryable_statuses: RetryableStatuses,
+}
+
+impl<RS: RetryStrategy> RetryRequest<RS> {
+    /// Retries `POST`, `PATCH` and other non-idempotent requests too,
+    /// e.g. when the server deduplicates them by an idempotency key
+    pub fn retry_non_idempotent(mut self) -> Self {
+        self.retry_non_idempotent = true;
+        self
+    }
+
+    /// Retries responses with `statuses` instead of `5xx` and `429 Too Many Requests`
+    pub fn retryable_statuses(mut self, statuses: RetryableStatuses) -> Self {
+        self.retryable_statuses = statuses;
+        self
+    }
+
+    /// Sends the request, turning error statuses into errors
+    /// with [error_for_status](Response::error_for_status)
+    pub async fn send(self) -> Result<Response, RetryError<reqwest::Error>> {
+        let Self { builder, retry_strategy, retry_non_idempotent, retryable_statuses } = self;
+        let method = builder.try_clone().and_then(|b| b.build().ok()).map(|r| r.method().clone());
+        let retriable = match method {
+            Some(method) => retry_non_idempotent || is_idempotent(&method),
+            // the body cannot be cloned
+            None => false,
+        };
+        let mut builder = Some(builder);
+        RetryFuture::new(
+            move || {
+                let attempt = if retriable {
+                    builder.as_ref().and_then(RequestBuilder::try_clone)
+                } else {
+                    builder.take()
+                };
+                let retryable_statuses = retryable_statuses.clone();
+                async move {
+                    // not retriable requests are sent only once
+                    let attempt = attempt.expect("request is sent once");
+                    attempt.send().await.and_then(Response::error_for_status).map_err(|e| {
+                        let retry = match e.status() {
+                            Some(status) => retryable_statuses.contains(status),
+                            None => matches!(classify_reqwest_error(&e), RetryPolicy::Retry(_)),
+                        };
+                        if retry && retriable {
+                            RetryPolicy::Retry(Some(Error::new(e)))
+                        } else {
+                            RetryPolicy::Fail(e)
+                        }
+                    })
+                }
+            },
+            retry_strategy,
+        )
+        .await
+    }
+}
+
+/// Set of response statuses which are retried by [RetryRequest]
+///
+/// ## Examples
+///
+/// ```rust
+/// use reqwest::StatusCode;
+/// use retry_future::RetryableStatuses;
+///
+/// let statuses = RetryableStatuses::default()
+///     .status(StatusCode::REQUEST_TIMEOUT)
+///     .range(StatusCode::BAD_GATEWAY..=StatusCode::GATEWAY_TIMEOUT);
+/// assert!(statuses.contains(StatusCode::REQUEST_TIMEOUT));
+/// assert!(statuses.contains(StatusCode::TOO_MANY_REQUESTS));
+/// assert!(!statuses.contains(StatusCode::NOT_FOUND));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryableStatuses {
+    ranges: Vec<RangeInclusive<u16>>,
+}
+
+impl RetryableStatuses {
+    /// Creates an empty set, so that no status is retried
+    pub fn new() -> Self {
+        Self { ranges: Vec::new() }
+    }
+
+    /// Adds a single status to the set
+    pub fn status(self, status: StatusCode) -> Self {
+        self.range(status..=status)
+    }
+
+    /// Adds all statuses from `range` to the set
+    pub fn range(mut self, range: RangeInclusive<StatusCode>) -> Self {
+        self.ranges.push(range.start().as_u16()..=range.end().as_u16());
+        self
+    }
+
+    pub fn contains(&self, status: StatusCode) -> bool {
+        self.ranges.iter().any(|range| range.contains(&status.as_u16()))
+    }
+}
+
+/// `5xx` and `429 Too Many Requests` statuses, see [classify_reqwest_error]
+impl Default for RetryableStatuses {
+    fn default() -> Self {
+        Self::new().status(StatusCode::TOO_MANY_REQUESTS).range(
+            StatusCode::INTERNAL_SERVER_ERROR..=StatusCode::from_u16(599).expect("valid status"),
+        )
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    [Method::GET, Method::HEAD, Method::OPTIONS, Method::TRACE, Method::PUT, Method::DELETE]
+        .contains(method)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LinearRetryStrategy, StopReason};
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Responds with `503 Service Unavailable` to every request
+    async fn unavailable() -> (SocketAddr, Arc<AtomicUsize>) {
+        respond_with(503).await
+    }
+
+    /// Responds with `status` to every request
+    async fn respond_with(status: u16) -> (SocketAddr, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        tokio::spawn({
+            let requests = requests.clone();
+            async move {
+                loop {
+                    let (mut socket, _) = listener.accept().await.unwrap();
+                    requests.fetch_add(1, Ordering::SeqCst);
+                    let _ = socket.read(&mut [0; 1024]).await;
+                    let response = format!(
+                        "HTTP/1.1 {status} X\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                    );
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                }
+            }
+        });
+        (addr, requests)
+    }
+
+    fn strategy() -> LinearRetryStrategy {
+        LinearRetryStrategy::new().max_attempts(2).delay_between_retries(Duration::ZERO)
+    }
+
+    #[tokio::test]
+    async fn get_is_retried() {
+        let (addr, requests) = unavailable().await;
+        let error = reqwest::Client::new()
+            .get(format!("http://{addr}"))
+            .retry(strategy())
+            .send()
+            .await
+            .unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        assert_eq!(3, requests.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn post_is_not_retried() {
+        let (addr, requests) = unavailable().await;
+        let error = reqwest::Client::new()
+            .post(format!("http://{addr}"))
+            .body("order")
+            .retry(strategy())
+            .send()
+            .await
+            .unwrap_err();
+        assert_eq!(StopReason::Fail, error.reason());
+        let status = error.into_fail().unwrap().status();
+        assert_eq!(Some(reqwest::StatusCode::SERVICE_UNAVAILABLE), status);
+        assert_eq!(1, requests.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn retry_non_idempotent() {
+        let (addr, requests) = unavailable().await;
+        let error = reqwest::Client::new()
+            .post(format!("http://{addr}"))
+            .body("order")
+            .retry(strategy())
+            .retry_non_idempotent()
+            .send()
+            .await
+            .unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        assert_eq!(3, requests.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn retryable_statuses() {
+        let default = RetryableStatuses::default();
+        for status in [429, 500, 503, 599] {
+            assert!(default.contains(StatusCode::from_u16(status).unwrap()), "{status}");
+        }
+        for status in [200, 404, 408, 428, 430] {
+            assert!(!default.contains(StatusCode::from_u16(status).unwrap()), "{status}");
+        }
+
+        let custom = RetryableStatuses::new()
+            .status(StatusCode::REQUEST_TIMEOUT)
+            .range(StatusCode::BAD_GATEWAY..=StatusCode::GATEWAY_TIMEOUT);
+        for status in [408, 502, 503, 504] {
+            assert!(custom.contains(StatusCode::from_u16(status).unwrap()), "{status}");
+        }
+        for status in [429, 500, 501, 505] {
+            assert!(!custom.contains(StatusCode::from_u16(status).unwrap()), "{status}");
+        }
+        assert!(!RetryableStatuses::new().contains(StatusCode::SERVICE_UNAVAILABLE));
+    }
+
+    #[tokio::test]
+    async fn custom_retryable_statuses() {
+        let (addr, requests) = respond_with(408).await;
+        let error = reqwest::Client::new()
+            .get(format!("http://{addr}"))
+            .retry(strategy())
+            .retryable_statuses(RetryableStatuses::default().status(StatusCode::REQUEST_TIMEOUT))
+            .send()
+            .await
+            .unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        assert_eq!(3, requests.load(Ordering::SeqCst));
+
+        let (addr, requests) = unavailable().await;
+        let error = reqwest::Client::new()
+            .get(format!("http://{addr}"))
+            .retry(strategy())
+            .retryable_statuses(RetryableStatuses::new().status(StatusCode::REQUEST_TIMEOUT))
+            .send()
+            .await
+            .unwrap_err();
+        assert_eq!(StopReason::Fail, error.reason());
+        assert_eq!(1, requests.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn classifies_reqwest_errors() {
+        let client = reqwest::Client::new();
+
+        let builder = client.get("not a url").send().await.unwrap_err();
+        assert!(matches!(classify_reqwest_error(&builder), RetryPolicy::Fail(())));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let refused = client.get(format!("http://{addr}")).send().await.unwrap_err();
+        assert!(refused.is_connect());
+        assert!(matches!(classify_reqwest_error(&refused), RetryPolicy::Retry(Some(_))));
+
+        // accepts connections but never responds
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let timeout = client
+            .get(format!("http://{addr}"))
+            .timeout(Duration::from_millis(10))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(timeout.is_timeout());
+        assert!(matches!(classify_reqwest_error(&timeout), RetryPolicy::Retry(Some(_))));
+        drop(listener);
+
+        for (status, retry) in [(503, true), (429, true), (404, false), (400, false)] {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let response = format!("HTTP/1.1 {status} X\r\ncontent-length: 0\r\n\r\n");
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+            let response = client.get(format!("http://{addr}")).send().await.unwrap();
+            let err = response.error_for_status().unwrap_err();
+            match classify_reqwest_error(&err) {
+                RetryPolicy::Retry(Some(_)) => assert!(retry, "{status} must fail"),
+                RetryPolicy::Fail(()) => assert!(!retry, "{status} must be retried"),
+                policy => panic!("unexpected {policy:?}"),
+            }
+        }
+    }
+}
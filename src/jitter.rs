@@ -0,0 +1,82 @@
+use rand::Rng;
+use std::time::Duration;
+
+/// Randomization of delays returned by a [RetryStrategy](crate::RetryStrategy),
+/// so that many futures failing at the same time do not retry simultaneously
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Jitter {
+    /// Random duration from `0` up to the delay itself
+    Full,
+    /// Delay plus random duration from `0` up to the given one
+    Additive(Duration),
+    /// Half of the delay plus random duration from `0` up to the other half,
+    /// i.e. `delay / 2 + random(0, delay / 2)`, which keeps a guaranteed minimum
+    Equal,
+}
+
+impl Jitter {
+    /// Randomizes `delay` according to the variant.
+    ///
+    /// Any `rng` can be used, e.g. a seeded one for reproducible delays
+    pub fn apply<R: Rng + ?Sized>(&self, delay: Duration, rng: &mut R) -> Duration {
+        match *self {
+            Jitter::Full => rng.gen_range(Duration::ZERO..=delay),
+            Jitter::Additive(max) => delay.saturating_add(rng.gen_range(Duration::ZERO..=max)),
+            Jitter::Equal => {
+                let half = delay / 2;
+                half + rng.gen_range(Duration::ZERO..=delay - half)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            assert!(Jitter::Full.apply(Duration::from_secs(1), &mut rng) <= Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn additive() {
+        let mut rng = rand::thread_rng();
+        let jitter = Jitter::Additive(Duration::from_millis(100));
+        for _ in 0..100 {
+            let delay = jitter.apply(Duration::from_secs(1), &mut rng);
+            assert!(delay >= Duration::from_secs(1));
+            assert!(delay <= Duration::from_millis(1100));
+        }
+    }
+
+    #[test]
+    fn equal() {
+        let mut rng = rand::thread_rng();
+        for delay in [Duration::from_secs(1), Duration::from_nanos(3), Duration::MAX] {
+            for _ in 0..100 {
+                let jittered = Jitter::Equal.apply(delay, &mut rng);
+                assert!(jittered >= delay / 2);
+                assert!(jittered <= delay);
+            }
+        }
+    }
+
+    #[test]
+    fn seeded() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let delays = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..10)
+                .map(|_| Jitter::Equal.apply(Duration::from_secs(1), &mut rng))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(delays(42), delays(42));
+        assert_ne!(delays(42), delays(43));
+    }
+}
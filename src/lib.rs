@@ -3,18 +3,54 @@
 //! retrying 500's errors from http requests or retrying something like "pseudo" successes from
 //! grpc requests.
 
+pub mod clock;
 pub mod error;
+pub mod events;
 mod future;
+mod handle;
+#[cfg(feature = "reqwest")]
+mod http;
+mod jitter;
+mod pool;
+mod retriable;
 mod retry_strategy;
+mod stream;
+mod template;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+pub mod timeline;
 
-pub use error::{Error, RetryError, TooManyAttempts};
-pub use future::RetryFuture;
+pub use clock::{Clock, TokioClock};
+#[allow(deprecated)]
+pub use error::TooManyAttempts;
+pub use error::{EnvError, Error, RetryError, RetryStop, StopReason, StrategyConfigError};
+pub use events::RetryEvent;
+#[cfg(feature = "blocking")]
+pub use future::Blocking;
+pub use future::{
+    AttemptStream, BoxRetryFuture, Decision, Failover, FutureFactory, RetryFuture, UntilSuccesses,
+};
+pub use handle::RetryHandle;
+#[cfg(feature = "reqwest")]
+pub use http::{classify_reqwest_error, RetryRequest, RetryRequestExt, RetryableStatuses};
+pub use jitter::Jitter;
+pub use pool::RetryPool;
+pub use retriable::{ResultExt, Retriable, RetriableExt};
+#[cfg(feature = "log")]
+pub use retry_strategy::Logged;
 pub use retry_strategy::{
-    ExponentialRetryStrategy, InfiniteRetryStrategy, LinearRetryStrategy, RetryStrategy,
+    default_strategy, saturating_mul, set_default_strategy, AsyncDecision, AsyncRetryStrategy,
+    AsyncStrategy, ExponentialRetryStrategy, FnStrategy, GrowingLinearStrategy,
+    InfiniteRetryStrategy, IteratorStrategy, LinearRetryStrategy, NoRetryStrategy, OneBased,
+    Planner, PollStrategy, Quantized, RateLimitedStrategy, RetryStrategy, RoundRobinStrategy,
+    RoundToMillis, SharedStrategy, SlidingWindowStrategy,
 };
 use std::fmt::Debug;
+pub use stream::{RetryItems, RetryStreamExt};
+pub use template::RetryFutureTemplate;
+pub use timeline::{AttemptRecord, Timeline};
 
-/// Return type of [inner future](crate::future::FutureFactory::Future)
+/// Return type of [inner future](crate::FutureFactory::Future)
 /// inside [RetryFuture](crate::future::RetryFuture)
 ///
 /// `Fail` variant means unrecoverable error
@@ -24,7 +60,62 @@ use std::fmt::Debug;
 ///
 /// If you want to provide some debug information about
 /// why a `Future` failed, you can construct [error](error::Error) youself.
-#[derive(Debug)]
+///
+/// `RetryPolicy` itself is not converted into [error](error::Error), so using `?` on
+/// `Result<T, RetryPolicy<E>>` propagates the policy unchanged, i.e. `Fail` stays `Fail`:
+///
+/// ```rust
+/// use retry_future::RetryPolicy;
+///
+/// fn check(status: u16) -> Result<(), RetryPolicy> {
+///     match status {
+///         500 => Err(RetryPolicy::Retry(None)),
+///         400 => Err(RetryPolicy::Fail(String::from("bad request"))),
+///         _ => Ok(()),
+///     }
+/// }
+///
+/// fn handle(status: u16) -> Result<u16, RetryPolicy> {
+///     check(status)?;
+///     Ok(status)
+/// }
+///
+/// assert!(matches!(handle(400), Err(RetryPolicy::Fail(_))));
+/// ```
+///
+/// Successful results which must be retried anyway, e.g. a job which is still pending,
+/// can be retried with [Decision::RetryNow] from [inspect](RetryFuture::inspect).
+/// If attempts run out, retrying stops with [ExhaustedWhilePending](StopReason::ExhaustedWhilePending)
+/// and the last value is returned by [last_ok](error::RetryError::last_ok):
+///
+/// ```rust
+/// use retry_future::{Decision, LinearRetryStrategy, RetryFuture, RetryPolicy, StopReason};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Progress(u32);
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let mut progress = 0;
+/// let error = RetryFuture::new(
+///     || {
+///         progress += 10;
+///         let progress = progress;
+///         async move { Ok::<_, RetryPolicy<()>>(Progress(progress)) }
+///     },
+///     LinearRetryStrategy::new().max_attempts(2),
+/// )
+/// .inspect(|result| match result {
+///     Ok(Progress(100)) => Decision::Continue,
+///     _ => Decision::RetryNow,
+/// })
+/// .await
+/// .unwrap_err();
+///
+/// assert_eq!(StopReason::ExhaustedWhilePending, error.reason());
+/// assert_eq!(Some(&Progress(30)), error.last_ok::<Progress>());
+/// # }
+/// ```
 pub enum RetryPolicy<E = String> {
     Retry(Option<Error>),
     /// Unrecoverable error which means that the [RetryFuture](crate::future::RetryFuture)
@@ -32,9 +123,147 @@ pub enum RetryPolicy<E = String> {
     Fail(E),
 }
 
+impl<E: From<std::io::Error>> RetryPolicy<E> {
+    /// Classifies IO error by its [kind](std::io::ErrorKind).
+    ///
+    /// Transient errors (`TimedOut`, `Interrupted`, `WouldBlock`, `ConnectionReset`)
+    /// are retried, others (e.g. `NotFound`, `PermissionDenied`) are unrecoverable.
+    pub fn from_io(err: std::io::Error) -> Self {
+        use std::io::ErrorKind;
+        match err.kind() {
+            ErrorKind::TimedOut
+            | ErrorKind::Interrupted
+            | ErrorKind::WouldBlock
+            | ErrorKind::ConnectionReset => Self::Retry(Some(Error::new(err))),
+            _ => Self::Fail(err.into()),
+        }
+    }
+}
+
+#[cfg(feature = "tonic")]
+impl<E: From<tonic::Status>> RetryPolicy<E> {
+    /// Classifies gRPC status by its [code](tonic::Code).
+    ///
+    /// `Unavailable`, `ResourceExhausted` and `Aborted` are retried, others are unrecoverable.
+    /// If the server sets `grpc-retry-pushback-ms` metadata, it is used as the delay
+    /// before the next attempt, see [Error::retry_after](crate::error::Error::retry_after).
+    pub fn from_tonic_status(status: tonic::Status) -> Self {
+        use tonic::Code;
+        match status.code() {
+            Code::Unavailable | Code::ResourceExhausted | Code::Aborted => {
+                let pushback = status
+                    .metadata()
+                    .get("grpc-retry-pushback-ms")
+                    .and_then(|value| value.to_str().ok()?.parse().ok())
+                    .map(std::time::Duration::from_millis);
+                let error = Error::new(status);
+                Self::Retry(Some(match pushback {
+                    Some(delay) => error.retry_after(delay),
+                    None => error,
+                }))
+            }
+            _ => Self::Fail(status.into()),
+        }
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl<E: From<String>> RetryPolicy<E> {
+    /// Classifies sqlx error, e.g. to retry a transaction.
+    ///
+    /// Serialization failures and deadlocks (SQLSTATE `40001` and `40P01`) as well as
+    /// IO errors and pool timeouts are retried. Others, including constraint violations,
+    /// are unrecoverable. Only the message of `err` is kept.
+    pub fn from_sqlx_error(err: &sqlx::Error) -> Self {
+        let retry = match err {
+            sqlx::Error::Database(e) => matches!(e.code().as_deref(), Some("40001" | "40P01")),
+            sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut => true,
+            _ => false,
+        };
+        if retry {
+            Self::Retry(Some(Error::msg(err.to_string())))
+        } else {
+            Self::Fail(err.to_string().into())
+        }
+    }
+}
+
+/// Shorthand for `RetryFuture::new(factory, retry_strategy).await`
+/// taking the strategy first.
+///
+/// ```rust
+/// use retry_future::{retry, LinearRetryStrategy, RetryPolicy};
+/// use std::time::Duration;
+///
+/// async fn step() -> Result<u32, std::io::Error> {
+///     Ok(1)
+/// }
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let strategy = LinearRetryStrategy::new().delay_between_retries(Duration::from_millis(1));
+/// let x = retry(strategy, || async {
+///     let x = step().await?;
+///     Ok::<_, RetryPolicy>(x + 1)
+/// })
+/// .await
+/// .unwrap();
+/// assert_eq!(2, x);
+/// # }
+/// ```
+pub async fn retry<RS, F, Fut, T, E>(retry_strategy: RS, factory: F) -> Result<T, RetryError<E>>
+where
+    RS: RetryStrategy,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, RetryPolicy<E>>>,
+    E: Debug,
+{
+    RetryFuture::new(factory, retry_strategy).await
+}
+
+/// Unlike derived `Debug`, shows whether the error inside `Retry` was returned early by using `?`,
+/// e.g. `Retry { early_returned: true, error: .. }`
+impl<E: Debug> Debug for RetryPolicy<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RetryPolicy::Retry(Some(e)) => f
+                .debug_struct("Retry")
+                .field("early_returned", &e.is_early_returned)
+                .field("error", &e.error)
+                .finish(),
+            RetryPolicy::Retry(None) => f.write_str("Retry(None)"),
+            RetryPolicy::Fail(e) => f.debug_tuple("Fail").field(e).finish(),
+        }
+    }
+}
+
+impl<E: Debug> RetryPolicy<E> {
+    /// One-line description of the policy which, unlike `Debug`,
+    /// does not include a backtrace of the error
+    pub(crate) fn summary(&self) -> String {
+        match self {
+            RetryPolicy::Retry(Some(e)) => format!("Retry({:#})", e.error),
+            RetryPolicy::Retry(None) => String::from("Retry(None)"),
+            RetryPolicy::Fail(e) => format!("Fail({e:?})"),
+        }
+    }
+}
+
+impl<E: Clone> RetryPolicy<E> {
+    /// See [Error::duplicate](crate::error::Error::duplicate)
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            RetryPolicy::Retry(maybe_error) => {
+                RetryPolicy::Retry(maybe_error.as_ref().map(Error::duplicate))
+            }
+            RetryPolicy::Fail(e) => RetryPolicy::Fail(e.clone()),
+        }
+    }
+}
+
 impl<E, T: Into<anyhow::Error>> From<T> for RetryPolicy<E> {
     fn from(t: T) -> Self {
-        Self::Retry(Some(Error { error: t.into(), is_early_returned: true }))
+        Self::Retry(Some(Error::from_anyhow(t.into(), true)))
     }
 }
 
@@ -58,6 +287,105 @@ macro_rules! retry {
     };
 }
 
+/// Creates a [RetryFuture](crate::RetryFuture) which runs the async block on every attempt,
+/// i.e. `retry_async!(strategy, async { .. })` expands to
+/// `RetryFuture::new(|| async move { .. }, strategy)`.
+///
+/// Since a new future is created from the block on every attempt, everything it captures is
+/// copied into each of them. So captured values must be `Copy`, e.g. references: to use
+/// a `String`, borrow it before the macro. The same goes for state shared between attempts,
+/// which has to be mutated through a shared reference, e.g. an atomic.
+///
+/// ```rust
+/// use retry_future::{retry_async, LinearRetryStrategy, RetryPolicy};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::time::Duration;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let name = String::from("retry_future");
+/// let name = &name;
+/// let attempts = AtomicUsize::new(0);
+/// let attempts = &attempts;
+///
+/// let len = retry_async!(
+///     LinearRetryStrategy::new().delay_between_retries(Duration::from_millis(1)),
+///     async {
+///         if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+///             return Err(RetryPolicy::Retry(None));
+///         }
+///         Ok::<_, RetryPolicy>(name.len())
+///     }
+/// )
+/// .await
+/// .unwrap();
+/// assert_eq!(12, len);
+/// assert_eq!(2, attempts.load(Ordering::SeqCst));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! retry_async {
+    ($strategy:expr, async $(move)? $body:block $(,)?) => {
+        $crate::RetryFuture::new(|| async move $body, $strategy)
+    };
+}
+
+/// Defines a unit struct implementing [RetryStrategy](crate::RetryStrategy)
+/// from a closure-like expression mapping `attempts_before` to a delay.
+///
+/// Once `attempts_before` reaches `max`, [RetryStop::MaxAttempts](crate::RetryStop::MaxAttempts)
+/// is returned. Errors propagated using `?` are retried.
+///
+/// ```rust
+/// use retry_future::{retry_strategy, RetryStrategy};
+/// use std::time::Duration;
+///
+/// retry_strategy!(pub MyStrategy, max: 3, |n| Duration::from_secs(n as u64 + 1));
+///
+/// let mut strategy = MyStrategy;
+/// assert_eq!(Duration::from_secs(1), strategy.check_attempt(0).unwrap());
+/// assert_eq!(Duration::from_secs(3), strategy.check_attempt(2).unwrap());
+/// assert!(strategy.check_attempt(3).is_err());
+/// assert_eq!("MyStrategy", strategy.name());
+/// ```
+#[macro_export]
+macro_rules! retry_strategy {
+    ($vis:vis $name:ident, max: $max:expr, |$attempts_before:ident| $delay:expr $(,)?) => {
+        #[derive(Debug, Default, Copy, Clone)]
+        $vis struct $name;
+
+        impl $crate::RetryStrategy for $name {
+            fn check_attempt(
+                &mut self,
+                $attempts_before: usize,
+            ) -> ::std::result::Result<::std::time::Duration, $crate::RetryStop> {
+                if $attempts_before >= $max {
+                    return ::std::result::Result::Err($crate::RetryStop::MaxAttempts);
+                }
+                ::std::result::Result::Ok($delay)
+            }
+
+            fn peek_next_delay(
+                &self,
+                $attempts_before: usize,
+            ) -> ::std::option::Option<::std::time::Duration> {
+                if $attempts_before >= $max {
+                    return ::std::option::Option::None;
+                }
+                ::std::option::Option::Some($delay)
+            }
+
+            fn retry_early_returned_errors(&self) -> bool {
+                true
+            }
+
+            fn name(&self) -> &'static str {
+                stringify!($name)
+            }
+        }
+    };
+}
+
 #[cfg(test)]
 mod tests {
     pub use super::*;
@@ -65,6 +393,8 @@ mod tests {
         future::{err, ok},
         TryFutureExt,
     };
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
     use std::time::Duration;
 
     struct MyRetryStrategy {
@@ -73,9 +403,9 @@ mod tests {
     }
 
     impl RetryStrategy for MyRetryStrategy {
-        fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, TooManyAttempts> {
+        fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
             if self.max_attempts == attempts_before {
-                Err(TooManyAttempts)
+                Err(RetryStop::MaxAttempts)
             } else {
                 self.counter.push(0);
                 Ok(Duration::from_millis(1))
@@ -102,7 +432,7 @@ mod tests {
             || err::<u8, _>(RetryPolicy::Fail("fail")),
             MyRetryStrategy { max_attempts: 2, counter: vec![] },
         );
-        let RetryPolicy::Fail(_) = f.await.unwrap_err().errors.last().unwrap() else {
+        let RetryPolicy::Fail(_) = f.await.unwrap_err().last_error().unwrap() else {
             panic!("Fail error must be returned")
         };
     }
@@ -129,4 +459,1773 @@ mod tests {
         f.await.unwrap_err();
         assert_eq!(0, retry_strategy.counter.len())
     }
+
+    #[tokio::test]
+    async fn test_error_sink() {
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(10);
+        let mut attempt = 0;
+        let f = RetryFuture::new(
+            || {
+                attempt += 1;
+                if attempt < 3 {
+                    err::<u8, _>(RetryPolicy::Retry(Some(Error::msg(format!("attempt {attempt}")))))
+                } else {
+                    err(RetryPolicy::Fail("fail"))
+                }
+            },
+            MyRetryStrategy { max_attempts: 5, counter: vec![] },
+        )
+        .error_sink(sender);
+        let collector = tokio::spawn(async move {
+            let mut errors = vec![];
+            while let Some(error) = receiver.recv().await {
+                errors.push(error);
+            }
+            errors
+        });
+        f.await.unwrap_err();
+        let errors = collector.await.unwrap();
+        assert_eq!(3, errors.len());
+        let RetryPolicy::Retry(Some(error)) = &errors[1] else {
+            panic!("Retry error must be sent")
+        };
+        assert_eq!("attempt 2", error.error.to_string());
+        let RetryPolicy::Fail("fail") = errors[2] else { panic!("Fail error must be sent") };
+    }
+
+    #[tokio::test]
+    async fn test_error_sink_dropped_receiver() {
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        drop(receiver);
+        let mut retry_strategy = MyRetryStrategy { max_attempts: 3, counter: vec![] };
+        let f = RetryFuture::new(
+            || err::<u8, RetryPolicy>(RetryPolicy::Retry(None)),
+            &mut retry_strategy,
+        )
+        .error_sink(sender);
+        assert_eq!(4, f.await.unwrap_err().len());
+        assert_eq!(3, retry_strategy.counter.len())
+    }
+
+    #[test]
+    fn test_from_io() {
+        use std::io::{Error as IoError, ErrorKind};
+        for kind in [
+            ErrorKind::TimedOut,
+            ErrorKind::Interrupted,
+            ErrorKind::WouldBlock,
+            ErrorKind::ConnectionReset,
+        ] {
+            let RetryPolicy::Retry(Some(e)) = RetryPolicy::<IoError>::from_io(kind.into()) else {
+                panic!("{kind:?} must be retried")
+            };
+            assert_eq!(kind, e.error.downcast_ref::<IoError>().unwrap().kind());
+        }
+        for kind in [ErrorKind::NotFound, ErrorKind::PermissionDenied, ErrorKind::InvalidData] {
+            let RetryPolicy::Fail(e) = RetryPolicy::<IoError>::from_io(kind.into()) else {
+                panic!("{kind:?} must fail")
+            };
+            assert_eq!(kind, e.kind());
+        }
+    }
+
+    #[cfg(feature = "tonic")]
+    #[test]
+    fn test_from_tonic_status() {
+        use tonic::{Code, Status};
+        for code in [Code::Unavailable, Code::ResourceExhausted, Code::Aborted] {
+            let RetryPolicy::Retry(Some(e)) =
+                RetryPolicy::<Status>::from_tonic_status(Status::new(code, "transient"))
+            else {
+                panic!("{code:?} must be retried")
+            };
+            assert_eq!(code, e.error.downcast_ref::<Status>().unwrap().code());
+            assert_eq!(None, e.retry_after);
+        }
+        for code in [Code::InvalidArgument, Code::NotFound, Code::PermissionDenied, Code::Internal]
+        {
+            let RetryPolicy::Fail(status) =
+                RetryPolicy::<Status>::from_tonic_status(Status::new(code, "fatal"))
+            else {
+                panic!("{code:?} must fail")
+            };
+            assert_eq!(code, status.code());
+        }
+    }
+
+    #[cfg(feature = "tonic")]
+    #[test]
+    fn test_from_tonic_status_pushback() {
+        use tonic::{Code, Status};
+        let mut status = Status::new(Code::ResourceExhausted, "slow down");
+        status.metadata_mut().insert("grpc-retry-pushback-ms", "250".parse().unwrap());
+        let RetryPolicy::Retry(Some(e)) = RetryPolicy::<Status>::from_tonic_status(status) else {
+            panic!("ResourceExhausted must be retried")
+        };
+        assert_eq!(Some(Duration::from_millis(250)), e.retry_after);
+    }
+
+    #[cfg(feature = "sqlx")]
+    #[test]
+    fn test_from_sqlx_error() {
+        use sqlx::error::{DatabaseError, ErrorKind};
+        use std::borrow::Cow;
+
+        #[derive(Debug)]
+        struct DbError {
+            code: &'static str,
+        }
+
+        impl std::fmt::Display for DbError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "database error {}", self.code)
+            }
+        }
+
+        impl std::error::Error for DbError {}
+
+        impl DatabaseError for DbError {
+            fn message(&self) -> &str {
+                "database error"
+            }
+
+            fn code(&self) -> Option<Cow<'_, str>> {
+                Some(Cow::Borrowed(self.code))
+            }
+
+            fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+                self
+            }
+
+            fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+                self
+            }
+
+            fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+                self
+            }
+
+            fn kind(&self) -> ErrorKind {
+                match self.code {
+                    "23505" => ErrorKind::UniqueViolation,
+                    "23503" => ErrorKind::ForeignKeyViolation,
+                    "23514" => ErrorKind::CheckViolation,
+                    _ => ErrorKind::Other,
+                }
+            }
+        }
+
+        let db_error = |code| sqlx::Error::Database(Box::new(DbError { code }));
+        for err in [
+            db_error("40001"),
+            db_error("40P01"),
+            sqlx::Error::Io(std::io::ErrorKind::ConnectionReset.into()),
+            sqlx::Error::PoolTimedOut,
+        ] {
+            let RetryPolicy::<String>::Retry(Some(e)) = RetryPolicy::from_sqlx_error(&err) else {
+                panic!("{err:?} must be retried")
+            };
+            assert_eq!(err.to_string(), e.error.to_string());
+        }
+        for err in
+            [db_error("23505"), db_error("23503"), db_error("23514"), sqlx::Error::RowNotFound]
+        {
+            let RetryPolicy::<String>::Fail(e) = RetryPolicy::from_sqlx_error(&err) else {
+                panic!("{err:?} must fail")
+            };
+            assert_eq!(err.to_string(), e);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_classifier() {
+        let mut retry_strategy = MyRetryStrategy { max_attempts: 5, counter: vec![] };
+        let f = RetryFuture::new(
+            || async {
+                std::fs::read("/nonexistent/retry_future")?;
+                Ok::<_, RetryPolicy<&str>>(())
+            },
+            &mut retry_strategy,
+        )
+        .classifier(|e| match e.downcast_ref::<std::io::Error>() {
+            Some(e) if e.kind() == std::io::ErrorKind::NotFound => RetryPolicy::Fail("not found"),
+            _ => RetryPolicy::Retry(None),
+        });
+        let error = f.await.unwrap_err();
+        assert_eq!(StopReason::Fail, error.reason());
+        assert_eq!(Some("not found"), error.into_fail());
+        assert_eq!(0, retry_strategy.counter.len());
+
+        let mut attempt = 0;
+        let f = RetryFuture::new(
+            || {
+                attempt += 1;
+                async { Ok::<_, RetryPolicy<&str>>("non-integer".parse::<u32>()?) }
+            },
+            LinearRetryStrategy::new().max_attempts(2).delay_between_retries(Duration::ZERO),
+        )
+        .classifier(|_| RetryPolicy::Retry(None));
+        let error = f.await.unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        assert!(matches!(error.last_error(), Some(RetryPolicy::Retry(Some(_)))));
+        assert_eq!(3, attempt);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[tokio::test]
+    async fn test_blocking() {
+        let mut attempt = 0;
+        let f = RetryFuture::new_blocking(
+            move || {
+                attempt += 1;
+                std::thread::sleep(Duration::from_millis(1));
+                if attempt < 3 {
+                    Err(RetryPolicy::<()>::Retry(None))
+                } else {
+                    Ok(attempt)
+                }
+            },
+            MyRetryStrategy { max_attempts: 5, counter: vec![] },
+        );
+        assert_eq!(3, f.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_boxed() {
+        fn parse(input: &'static str) -> BoxRetryFuture<u32, String> {
+            RetryFuture::new(
+                move || async move { Ok(input.parse::<u32>()?) },
+                MyRetryStrategy { max_attempts: 2, counter: vec![] },
+            )
+            .boxed()
+        }
+
+        assert_eq!(42, parse("42").await.unwrap());
+        assert_eq!(StopReason::EarlyReturned, parse("forty-two").await.unwrap_err().reason());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_with_strategy_for() {
+        let mut errors = vec!["server error", "rate limited", "server error", "rate limited"];
+        let f = RetryFuture::new(
+            || {
+                let result = errors
+                    .pop()
+                    .map_or(Ok(()), |e| Err(RetryPolicy::<()>::Retry(Some(Error::msg(e)))));
+                async move { result }
+            },
+            LinearRetryStrategy::new().delay_between_retries(Duration::from_millis(10)),
+        )
+        .with_strategy_for(
+            |e| matches!(e, RetryPolicy::Retry(Some(e)) if e.error.to_string() == "rate limited"),
+            LinearRetryStrategy::new().delay_between_retries(Duration::from_secs(1)),
+        )
+        .record_timeline();
+        let timeline = f.timeline().unwrap();
+        f.await.unwrap();
+        let delays: Vec<_> = timeline.records().into_iter().map(|record| record.delay).collect();
+        assert_eq!(
+            vec![
+                Some(Duration::from_secs(1)),
+                Some(Duration::from_millis(10)),
+                Some(Duration::from_secs(1)),
+                Some(Duration::from_millis(10)),
+            ],
+            delays
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_first_attempt_is_immediate() {
+        let started = tokio::time::Instant::now();
+        let mut attempts = vec![];
+        let f = RetryFuture::new(
+            || {
+                attempts.push(started.elapsed());
+                err::<(), _>(RetryPolicy::<()>::Retry(None))
+            },
+            LinearRetryStrategy::new()
+                .max_attempts(1)
+                .delay_between_retries(Duration::from_secs(5)),
+        );
+        f.await.unwrap_err();
+        assert_eq!(vec![Duration::ZERO, Duration::from_secs(5)], attempts);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_delay_before_first_attempt() {
+        let started = tokio::time::Instant::now();
+        let attempts = &std::sync::Mutex::new(vec![]);
+        let f = RetryFuture::new(
+            || async move {
+                attempts.lock().unwrap().push(started.elapsed());
+                Err::<(), _>(RetryPolicy::<()>::Retry(None))
+            },
+            LinearRetryStrategy::new()
+                .max_attempts(1)
+                .delay_between_retries(Duration::from_secs(5)),
+        )
+        .delay_before_first_attempt(Duration::from_secs(2));
+        f.await.unwrap_err();
+        assert_eq!(vec![Duration::from_secs(2), Duration::from_secs(7)], *attempts.lock().unwrap());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_until() {
+        let mut attempt = 0;
+        let f = RetryFuture::new(
+            || {
+                attempt += 1;
+                err::<(), _>(RetryPolicy::<()>::Retry(None))
+            },
+            LinearRetryStrategy::new().delay_between_retries(Duration::from_secs(1)),
+        )
+        .until(tokio::time::sleep(Duration::from_millis(2500)));
+        let started = tokio::time::Instant::now();
+        let error = f.await.unwrap_err();
+        assert_eq!(Duration::from_millis(2500), started.elapsed());
+        assert_eq!(StopReason::DeadlineExceeded, error.reason());
+        assert_eq!(3, error.len());
+        assert_eq!(3, attempt);
+    }
+
+    #[tokio::test]
+    async fn test_map_err() {
+        #[derive(Debug, PartialEq)]
+        enum DomainError {
+            Unavailable { attempts: usize },
+        }
+
+        let f = RetryFuture::new(
+            || err::<(), _>(RetryPolicy::<()>::Retry(None)),
+            MyRetryStrategy { max_attempts: 2, counter: vec![] },
+        )
+        .map_err(|e| DomainError::Unavailable { attempts: e.len() });
+        assert_eq!(Err(DomainError::Unavailable { attempts: 3 }), f.await);
+    }
+
+    #[tokio::test]
+    async fn test_on_success() {
+        let mut attempt = 0;
+        let attempts_before = Arc::new(AtomicUsize::new(usize::MAX));
+        let f = RetryFuture::new(
+            || {
+                attempt += 1;
+                let result = if attempt < 3 { Err(RetryPolicy::<()>::Retry(None)) } else { Ok(()) };
+                async move { result }
+            },
+            MyRetryStrategy { max_attempts: 5, counter: vec![] },
+        )
+        .on_success({
+            let attempts_before = attempts_before.clone();
+            move |n| attempts_before.store(n, Ordering::SeqCst)
+        });
+        f.await.unwrap();
+        assert_eq!(2, attempts_before.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_max_early_return_retries() {
+        let mut attempt = 0;
+        let f = RetryFuture::new(
+            || {
+                attempt += 1;
+                let attempt = attempt;
+                async move {
+                    if attempt <= 2 {
+                        Err::<(), _>(std::io::Error::other("early returned"))?;
+                    }
+                    Err::<(), _>(RetryPolicy::<()>::Retry(None))
+                }
+            },
+            LinearRetryStrategy::new().max_attempts(10).delay_between_retries(Duration::ZERO),
+        )
+        .max_early_return_retries(1);
+        let error = f.await.unwrap_err();
+        assert_eq!(StopReason::EarlyReturned, error.reason());
+        assert_eq!(2, error.len());
+    }
+
+    #[tokio::test]
+    async fn test_until_successes() {
+        let mut outcomes = vec![Ok(4), Err(()), Ok(3), Err(()), Err(()), Ok(2), Ok(1)].into_iter();
+        let f = RetryFuture::new(
+            || {
+                let outcome = outcomes.next().unwrap();
+                async move { outcome.map_err(|_| RetryPolicy::<()>::Retry(None)) }
+            },
+            MyRetryStrategy { max_attempts: 2, counter: vec![] },
+        )
+        .until_successes(3);
+        assert_eq!(vec![4, 3, 2], f.await.unwrap());
+
+        let mut outcomes = vec![Ok(1), Err(()), Err(()), Err(()), Ok(2)].into_iter();
+        let f = RetryFuture::new(
+            || {
+                let outcome = outcomes.next().unwrap();
+                async move { outcome.map_err(|_| RetryPolicy::<()>::Retry(None)) }
+            },
+            MyRetryStrategy { max_attempts: 2, counter: vec![] },
+        )
+        .until_successes(2);
+        let error = f.await.unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        assert_eq!(3, error.len());
+    }
+
+    #[tokio::test]
+    async fn test_catch_factory_panics() {
+        let mut calls = 0;
+        let f = RetryFuture::new(
+            || {
+                calls += 1;
+                if calls == 2 {
+                    panic!("second call");
+                }
+                async move {
+                    if calls < 3 {
+                        Err(RetryPolicy::<()>::Retry(None))
+                    } else {
+                        Ok(calls)
+                    }
+                }
+            },
+            MyRetryStrategy { max_attempts: 5, counter: vec![] },
+        )
+        .catch_factory_panics()
+        .record_timeline();
+        let timeline = f.timeline().unwrap();
+        assert_eq!(3, f.await.unwrap());
+        let records = timeline.records();
+        assert_eq!(2, records.len());
+        assert!(records[1].error.contains("factory panicked: second call"), "{}", records[1].error);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_starting_attempt() {
+        let mut attempt = 0;
+        let started = tokio::time::Instant::now();
+        let f = RetryFuture::new(
+            || {
+                attempt += 1;
+                let attempt = attempt;
+                async move {
+                    if attempt == 1 {
+                        Err(RetryPolicy::<()>::Retry(None))
+                    } else {
+                        Ok(())
+                    }
+                }
+            },
+            ExponentialRetryStrategy::new().max_attempts(5).initial_delay(Duration::from_secs(1)),
+        )
+        .starting_attempt(2);
+        f.await.unwrap();
+        // 1 and 2 seconds are skipped
+        assert_eq!(Duration::from_secs(4), started.elapsed());
+    }
+
+    #[tokio::test]
+    async fn test_annotate_exhaustion() {
+        let f = RetryFuture::new(
+            || async { Err::<(), _>(RetryPolicy::<()>::Retry(None)) },
+            MyRetryStrategy { max_attempts: 2, counter: vec![] },
+        )
+        .annotate_exhaustion();
+        let error = f.await.unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        assert_eq!(4, error.len());
+        let Some(RetryPolicy::Retry(Some(e))) = error.last_error() else { panic!("no annotation") };
+        assert_eq!("exceeded max attempts", e.error.to_string());
+
+        let f = RetryFuture::new(
+            || async { Err::<(), _>(RetryPolicy::Fail(())) },
+            MyRetryStrategy { max_attempts: 2, counter: vec![] },
+        )
+        .annotate_exhaustion();
+        assert_eq!(1, f.await.unwrap_err().len());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_on_cancel() {
+        let cancelled = Arc::new(AtomicUsize::new(0));
+        let f = |attempts: usize| {
+            let mut attempt = 0;
+            let cancelled = cancelled.clone();
+            RetryFuture::new(
+                move || {
+                    attempt += 1;
+                    let result = if attempt <= attempts {
+                        Err(RetryPolicy::<()>::Retry(None))
+                    } else {
+                        Ok(())
+                    };
+                    async move { result }
+                },
+                LinearRetryStrategy::new().delay_between_retries(Duration::from_secs(1)),
+            )
+            .on_cancel(move || {
+                cancelled.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+
+        tokio::time::timeout(Duration::from_millis(500), f(1)).await.unwrap_err();
+        assert_eq!(1, cancelled.load(Ordering::SeqCst));
+
+        f(1).await.unwrap();
+        f(0).await.unwrap();
+        assert_eq!(1, cancelled.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_yield_between_attempts() {
+        /// Clock whose delays have always passed
+        struct ImmediateClock;
+
+        impl Clock for ImmediateClock {
+            type Sleep = std::future::Ready<()>;
+
+            fn now(&self) -> std::time::Instant {
+                std::time::Instant::now()
+            }
+
+            fn sleep(&self, _duration: Duration) -> Self::Sleep {
+                std::future::ready(())
+            }
+        }
+
+        async fn pending_polls(yield_between_attempts: bool) -> usize {
+            let mut attempt = 0;
+            let f = RetryFuture::new(
+                move || {
+                    attempt += 1;
+                    let result =
+                        if attempt <= 3 { Err(RetryPolicy::<()>::Retry(None)) } else { Ok(()) };
+                    async move { result }
+                },
+                LinearRetryStrategy::new().delay_between_retries(Duration::from_millis(1)),
+            )
+            .with_clock(ImmediateClock);
+            let f = if yield_between_attempts { f.yield_between_attempts() } else { f };
+            let mut f = std::pin::pin!(f);
+            let mut pending = 0;
+            while futures::poll!(f.as_mut()).is_pending() {
+                pending += 1;
+            }
+            pending
+        }
+
+        assert_eq!(0, pending_polls(false).await);
+        assert_eq!(3, pending_polls(true).await);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_async_retry_strategy() {
+        /// Asks an imaginary remote service for every decision
+        struct RemoteStrategy {
+            asked: Arc<AtomicUsize>,
+        }
+
+        impl AsyncRetryStrategy for RemoteStrategy {
+            fn check_attempt(&mut self, attempts_before: usize) -> AsyncDecision {
+                let asked = self.asked.clone();
+                Box::pin(async move {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    asked.fetch_add(1, Ordering::SeqCst);
+                    if attempts_before < 2 {
+                        Ok(Duration::from_secs(2))
+                    } else {
+                        Err(RetryStop::MaxAttempts)
+                    }
+                })
+            }
+
+            fn retry_early_returned_errors(&self) -> bool {
+                true
+            }
+        }
+
+        let asked = Arc::new(AtomicUsize::new(0));
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let started = tokio::time::Instant::now();
+        let error = RetryFuture::new(
+            || {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                err::<(), _>(RetryPolicy::<()>::Retry(None))
+            },
+            AsyncStrategy(RemoteStrategy { asked: asked.clone() }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        assert_eq!(3, attempts.load(Ordering::SeqCst));
+        assert_eq!(3, asked.load(Ordering::SeqCst));
+        // three decisions and two delays between attempts
+        assert_eq!(Duration::from_secs(7), started.elapsed());
+    }
+
+    /// Decides after a second of asking, retrying `retries` times without a delay
+    struct SlowDecisions {
+        retries: usize,
+        asked: Arc<std::sync::Mutex<Vec<usize>>>,
+    }
+
+    impl AsyncRetryStrategy for SlowDecisions {
+        fn check_attempt(&mut self, attempts_before: usize) -> AsyncDecision {
+            self.asked.lock().unwrap().push(attempts_before);
+            let retries = self.retries;
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                match attempts_before < retries {
+                    true => Ok(Duration::ZERO),
+                    false => Err(RetryStop::MaxAttempts),
+                }
+            })
+        }
+
+        fn retry_early_returned_errors(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_async_strategy_for() {
+        let asked = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut errors = vec!["server error", "rate limited", "rate limited"];
+        let started = tokio::time::Instant::now();
+        let result = RetryFuture::new(
+            || {
+                let result = errors
+                    .pop()
+                    .map_or(Ok(()), |e| Err(RetryPolicy::<()>::Retry(Some(Error::msg(e)))));
+                async move { result }
+            },
+            LinearRetryStrategy::new().delay_between_retries(Duration::from_millis(10)),
+        )
+        .with_strategy_for(
+            |e| matches!(e, RetryPolicy::Retry(Some(e)) if e.error.to_string() == "rate limited"),
+            AsyncStrategy(SlowDecisions { retries: 5, asked: asked.clone() }),
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(vec![0, 1], *asked.lock().unwrap());
+        assert_eq!(Duration::from_millis(2010), started.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_async_strategy_hedged() {
+        let asked = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut attempt = 0;
+        let started = tokio::time::Instant::now();
+        let result = RetryFuture::new(
+            || {
+                attempt += 1;
+                let attempt = attempt;
+                async move {
+                    match attempt {
+                        1 => tokio::time::sleep(Duration::from_millis(150)).await,
+                        2 => tokio::time::sleep(Duration::from_millis(200)).await,
+                        _ => return Ok(attempt),
+                    }
+                    Err(RetryPolicy::<()>::Retry(None))
+                }
+            },
+            AsyncStrategy(SlowDecisions { retries: 5, asked: asked.clone() }),
+        )
+        .hedged(Duration::from_millis(100), 2)
+        .await;
+        // the first attempt fails while the second one is in flight,
+        // its decision is awaited before the second failure is decided
+        assert_eq!(3, result.unwrap());
+        assert_eq!(vec![0, 1], *asked.lock().unwrap());
+        assert_eq!(Duration::from_millis(2150), started.elapsed());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test(start_paused = true)]
+    async fn test_in_span() {
+        use std::collections::HashMap;
+        use std::fmt::Write;
+        use std::sync::Mutex;
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing_subscriber::layer::{Context, SubscriberExt};
+        use tracing_subscriber::registry::LookupSpan;
+
+        /// Name, name of the parent and recorded fields
+        type SpanRecord = (String, Option<String>, String);
+
+        #[derive(Default, Clone)]
+        struct Spans(Arc<Mutex<HashMap<u64, SpanRecord>>>);
+
+        struct Fields<'a>(&'a mut String);
+
+        impl Visit for Fields<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                write!(self.0, "{}={value:?} ", field.name()).unwrap();
+            }
+        }
+
+        impl<S: tracing::Subscriber + for<'a> LookupSpan<'a>> tracing_subscriber::Layer<S> for Spans {
+            fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+                let parent = ctx.span(id).and_then(|span| span.parent()).map(|p| p.name().into());
+                let mut fields = String::new();
+                attrs.record(&mut Fields(&mut fields));
+                let span = (attrs.metadata().name().into(), parent, fields);
+                self.0.lock().unwrap().insert(id.into_u64(), span);
+            }
+
+            fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+                let mut spans = self.0.lock().unwrap();
+                values.record(&mut Fields(&mut spans.get_mut(&id.into_u64()).unwrap().2));
+            }
+        }
+
+        let spans = Spans::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(spans.clone()));
+        let span = tracing::info_span!(
+            "caller",
+            retry.outcome = tracing::field::Empty,
+            retry.attempts = tracing::field::Empty,
+        );
+        let mut attempt = 0;
+        RetryFuture::new(
+            move || {
+                attempt += 1;
+                async move {
+                    tracing::info_span!("inner").in_scope(|| ());
+                    if attempt < 3 {
+                        Err(RetryPolicy::<()>::Retry(None))
+                    } else {
+                        Ok(())
+                    }
+                }
+            },
+            LinearRetryStrategy::new().delay_between_retries(Duration::from_secs(1)),
+        )
+        .in_span(span)
+        .await
+        .unwrap();
+
+        let mut spans: Vec<_> = spans.0.lock().unwrap().drain().collect();
+        spans.sort_by_key(|(id, _)| *id);
+        let spans: Vec<_> = spans.into_iter().map(|(_, span)| span).collect();
+        let expected = [
+            ("caller", None, "retry.outcome=\"success\" retry.attempts=3 "),
+            ("retry_attempt", Some("caller"), "attempt=1 outcome=\"error\" delay=1s "),
+            ("inner", Some("retry_attempt"), ""),
+            ("retry_attempt", Some("caller"), "attempt=2 outcome=\"error\" delay=1s "),
+            ("inner", Some("retry_attempt"), ""),
+            ("retry_attempt", Some("caller"), "attempt=3 outcome=\"success\" "),
+            ("inner", Some("retry_attempt"), ""),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(name, parent, fields)| {
+                (name.to_string(), parent.map(String::from), fields.to_string())
+            })
+            .collect();
+        assert_eq!(expected, spans);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stop_on_repeated_errors() {
+        let mut attempt = 0;
+        let error = RetryFuture::new(
+            move || {
+                attempt += 1;
+                // the first error differs, then the same one repeats
+                let message = if attempt == 1 { "connecting" } else { "invalid token" };
+                err::<(), _>(RetryPolicy::<()>::Retry(Some(Error::msg(message))))
+            },
+            LinearRetryStrategy::new().max_attempts(10),
+        )
+        .stop_on_repeated_errors(3)
+        .await
+        .unwrap_err();
+        assert_eq!(StopReason::RepeatedErrors, error.reason());
+        assert_eq!(4, error.len());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stop_on_repeated_errors_resets() {
+        // a success right before the limit is returned
+        let mut attempt = 0;
+        let value = RetryFuture::new(
+            move || {
+                attempt += 1;
+                match attempt {
+                    1 | 2 => {
+                        err::<_, RetryPolicy<()>>(RetryPolicy::Retry(Some(Error::msg("busy"))))
+                    }
+                    _ => ok(attempt),
+                }
+            },
+            LinearRetryStrategy::new().max_attempts(10),
+        )
+        .stop_on_repeated_errors(3)
+        .await
+        .unwrap();
+        assert_eq!(3, value);
+
+        // retried `Ok` values are not errors, so polling is never stopped
+        let mut statuses = vec!["running"; 5].into_iter();
+        let status = RetryFuture::new(
+            move || ok::<_, RetryPolicy<()>>(statuses.next().unwrap_or("done")),
+            LinearRetryStrategy::new().max_attempts(10),
+        )
+        .inspect(|status| match status {
+            Ok("done") => Decision::Continue,
+            _ => Decision::RetryNow,
+        })
+        .stop_on_repeated_errors(2)
+        .await
+        .unwrap();
+        assert_eq!("done", status);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 2 errors")]
+    fn test_stop_on_repeated_errors_of_one() {
+        drop(
+            RetryFuture::<_, _, _, ()>::new(
+                || ok::<(), RetryPolicy<()>>(()),
+                LinearRetryStrategy::new(),
+            )
+            .stop_on_repeated_errors(1),
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_reset_backoff_after() {
+        let started = tokio::time::Instant::now();
+        let starts = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut attempt = 0;
+        let error = RetryFuture::new(
+            {
+                let starts = starts.clone();
+                move || {
+                    attempt += 1;
+                    starts.lock().unwrap().push(started.elapsed());
+                    async move {
+                        // the third attempt works for an hour before failing
+                        if attempt == 3 {
+                            tokio::time::sleep(Duration::from_secs(3600)).await;
+                        }
+                        Err::<(), _>(RetryPolicy::<()>::Retry(None))
+                    }
+                }
+            },
+            ExponentialRetryStrategy::new().max_attempts(4).initial_delay(Duration::from_secs(1)),
+        )
+        .reset_backoff_after(Duration::from_secs(60))
+        .await
+        .unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        let secs: Vec<_> = starts.lock().unwrap().iter().map(Duration::as_secs).collect();
+        // delays of 1s and 2s, then the backoff and max attempts start over after the quiet hour
+        assert_eq!(vec![0, 1, 3, 3604, 3606, 3610, 3618], secs);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_into_attempt_stream() {
+        use futures::StreamExt;
+
+        let attempts = |fail_at: usize| {
+            let mut attempt = 0;
+            RetryFuture::new(
+                move || {
+                    attempt += 1;
+                    let result = match attempt {
+                        n if n == fail_at => Err(RetryPolicy::Fail(n)),
+                        3 => Ok(3),
+                        n => Err(RetryPolicy::Retry(Some(Error::msg(format!("attempt {n}"))))),
+                    };
+                    async move { result }
+                },
+                LinearRetryStrategy::new().max_attempts(5),
+            )
+            .into_attempt_stream()
+            .map(|outcome| match outcome {
+                Ok(t) => format!("Ok({t})"),
+                Err(err) => err.summary(),
+            })
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec!["Retry(attempt 1)", "Retry(attempt 2)", "Ok(3)"],
+            attempts(usize::MAX).await
+        );
+        assert_eq!(vec!["Retry(attempt 1)", "Fail(2)"], attempts(2).await);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_attempt_stream_keeps_errors() {
+        use futures::StreamExt;
+
+        let attempts = |statuses: Vec<&'static str>| {
+            let mut statuses = statuses.into_iter();
+            RetryFuture::new(
+                move || ok::<_, RetryPolicy<()>>(statuses.next().unwrap_or("running")),
+                LinearRetryStrategy::new().max_attempts(3),
+            )
+            .inspect(|_| Decision::RetryNow)
+            .stall_detector(2, |previous, next| previous == next)
+            .annotate_exhaustion()
+            .into_attempt_stream()
+            .map(|outcome| outcome.unwrap_err().summary())
+            .collect::<Vec<_>>()
+        };
+        let retried = "Retry(inspect decided RetryNow)";
+        // the detector compares values of errors which are already yielded
+        assert_eq!(vec![retried; 3], attempts(vec![]).await);
+        // the annotation is yielded once after the errors
+        assert_eq!(
+            vec![retried, retried, retried, retried, "Retry(exceeded max attempts)"],
+            attempts(vec!["queued", "running", "queued"]).await
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_event_bus() {
+        let (sender, mut receiver) = tokio::sync::broadcast::channel(16);
+        let error = RetryFuture::new(
+            || err::<(), _>(RetryPolicy::<()>::Retry(Some(Error::msg("unavailable")))),
+            LinearRetryStrategy::new()
+                .max_attempts(1)
+                .delay_between_retries(Duration::from_secs(1)),
+        )
+        .with_event_bus(sender)
+        .await
+        .unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+
+        let failed = |attempt, delay| RetryEvent::AttemptFailed {
+            attempt,
+            error: String::from("Retry(unavailable)"),
+            delay,
+        };
+        let mut events = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            events.push(event);
+        }
+        assert_eq!(
+            vec![
+                RetryEvent::AttemptStarted { attempt: 0 },
+                failed(0, Some(Duration::from_secs(1))),
+                RetryEvent::AttemptStarted { attempt: 1 },
+                failed(1, None),
+                RetryEvent::GaveUp { reason: StopReason::TooManyAttempts },
+            ],
+            events
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_and_then() {
+        let mut bodies = vec!["42", "{ broken"].into_iter().rev();
+        let number = RetryFuture::new(
+            move || ok::<_, RetryPolicy<String>>(bodies.next().unwrap()),
+            LinearRetryStrategy::new(),
+        )
+        .and_then(|body| body.parse::<u32>().map_err(|e| RetryPolicy::Retry(Some(Error::new(e)))))
+        .await
+        .unwrap();
+        assert_eq!(42, number);
+
+        let error =
+            RetryFuture::new(|| ok::<_, RetryPolicy<String>>("-1"), LinearRetryStrategy::new())
+                .and_then(|body| body.parse::<u32>().map_err(|e| RetryPolicy::Fail(e.to_string())))
+                .await
+                .unwrap_err();
+        assert_eq!(StopReason::Fail, error.reason());
+        assert_eq!(1, error.len());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_max_attempts_for() {
+        fn is_hard(err: &RetryPolicy<&'static str>) -> bool {
+            matches!(err, RetryPolicy::Retry(Some(e)) if e.error.to_string() == "hard")
+        }
+
+        async fn attempts(errors: &'static [&'static str]) -> (StopReason, usize) {
+            let mut errors = errors.iter();
+            let error = RetryFuture::new(
+                move || {
+                    let error = Error::msg(*errors.next().unwrap_or(&"soft"));
+                    err::<(), _>(RetryPolicy::Retry(Some(error)))
+                },
+                InfiniteRetryStrategy::new(),
+            )
+            .max_attempts_for(is_hard, 1)
+            .max_attempts_for(|err| !is_hard(err), 5)
+            .await
+            .unwrap_err();
+            (error.reason(), error.len())
+        }
+
+        assert_eq!((StopReason::TooManyAttempts, 6), attempts(&[]).await);
+        assert_eq!((StopReason::TooManyAttempts, 2), attempts(&["hard", "hard"]).await);
+        assert_eq!(
+            (StopReason::TooManyAttempts, 4),
+            attempts(&["soft", "hard", "soft", "hard"]).await
+        );
+    }
+
+    #[cfg(feature = "log")]
+    #[tokio::test(start_paused = true)]
+    async fn test_give_up_warning() {
+        let logs = crate::test_util::captured_logs();
+        RetryFuture::new(
+            || err::<(), _>(RetryPolicy::<()>::Retry(Some(Error::msg("warned-on-give-up")))),
+            LinearRetryStrategy::new().max_attempts(2),
+        )
+        .await
+        .unwrap_err();
+        let warnings: Vec<_> = logs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| record.contains("warned-on-give-up"))
+            .filter(|record| record.starts_with("WARN Gave up after"))
+            .cloned()
+            .collect();
+        assert_eq!(
+            vec![
+                "WARN Gave up after 3 attempts - TooManyAttempts, last error - Retry(warned-on-give-up)"
+            ],
+            warnings
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_share_errors() {
+        let errors = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut attempt = 0;
+        let f = RetryFuture::new(
+            move || {
+                attempt += 1;
+                err::<(), _>(RetryPolicy::Retry(Some(Error::msg(format!("attempt {attempt}")))))
+            },
+            InfiniteRetryStrategy::new().duration_between_retries(Duration::from_secs(1)),
+        )
+        .share_errors(errors.clone());
+        // dropped while sleeping after the third attempt
+        let cancelled = tokio::time::timeout(Duration::from_millis(2500), f).await;
+        assert!(cancelled.is_err());
+
+        let errors: Vec<_> =
+            errors.lock().unwrap().iter().map(|e: &RetryPolicy<String>| e.summary()).collect();
+        assert_eq!(vec!["Retry(attempt 1)", "Retry(attempt 2)", "Retry(attempt 3)"], errors);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_require_streak() {
+        let outcomes = [true, true, false, true, false, true, true, true, false];
+        let mut outcomes = outcomes.into_iter().enumerate();
+        let attempt = RetryFuture::new(
+            move || {
+                let (attempt, success) = outcomes.next().unwrap();
+                let result =
+                    if success { Ok(attempt) } else { Err(RetryPolicy::<()>::Retry(None)) };
+                async move { result }
+            },
+            LinearRetryStrategy::new().max_attempts(3),
+        )
+        .require_streak(3)
+        .await
+        .unwrap();
+        assert_eq!(7, attempt);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_inspect_continue() {
+        let mut results = vec![Ok(1), Err(RetryPolicy::Retry(None))];
+        let seen = AtomicUsize::new(0);
+        let start = tokio::time::Instant::now();
+        let value = RetryFuture::new(
+            move || std::future::ready(results.pop().unwrap()),
+            LinearRetryStrategy::new().delay_between_retries(Duration::from_secs(1)),
+        )
+        .inspect(|_: &Result<u32, RetryPolicy<()>>| {
+            seen.fetch_add(1, Ordering::Relaxed);
+            Decision::Continue
+        })
+        .await
+        .unwrap();
+        assert_eq!(1, value);
+        assert_eq!(2, seen.load(Ordering::Relaxed));
+        assert_eq!(Duration::from_secs(1), start.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_inspect_retry_now() {
+        let mut results = vec![Ok(2), Err(RetryPolicy::Fail("bad")), Ok(1)];
+        let start = tokio::time::Instant::now();
+        let value = RetryFuture::new(
+            move || std::future::ready(results.pop().unwrap()),
+            LinearRetryStrategy::new().delay_between_retries(Duration::from_secs(1)),
+        )
+        .inspect(|result| match result {
+            Ok(1) | Err(_) => Decision::RetryNow,
+            _ => Decision::Continue,
+        })
+        .await
+        .unwrap();
+        assert_eq!(2, value);
+        assert_eq!(Duration::ZERO, start.elapsed());
+
+        let mut attempt = 0u32;
+        let error = RetryFuture::new(
+            || {
+                attempt += 1;
+                std::future::ready(Ok::<_, RetryPolicy<&str>>(attempt))
+            },
+            LinearRetryStrategy::new().max_attempts(3),
+        )
+        .inspect(|_| Decision::RetryNow)
+        .await
+        .unwrap_err();
+        assert_eq!(StopReason::ExhaustedWhilePending, error.reason());
+        assert_eq!(Some(&4), error.last_ok::<u32>());
+        // every retried value is kept in its error
+        let retried: Vec<_> = error
+            .errors()
+            .iter()
+            .map(|err| match err {
+                RetryPolicy::Retry(Some(e)) => e.retried_ok::<u32>().copied(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec![Some(1), Some(2), Some(3), Some(4)], retried);
+
+        let error = RetryFuture::new(
+            || std::future::ready(Err::<(), _>(RetryPolicy::Fail(String::from("bad")))),
+            LinearRetryStrategy::new().max_attempts(1),
+        )
+        .inspect(|_| Decision::RetryNow)
+        .await
+        .unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        let Some(RetryPolicy::Retry(Some(last))) = error.last_error() else { unreachable!() };
+        assert_eq!(Some(&String::from("bad")), last.retried_fail::<String>());
+        assert_eq!(None, last.retried_ok::<String>());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_inspect_not_sync() {
+        use std::cell::Cell;
+
+        // values and errors only have to be `Send`
+        let mut attempt = 0u8;
+        let mut error = RetryFuture::new(
+            move || {
+                attempt += 1;
+                ok::<_, RetryPolicy<Cell<()>>>(Cell::new(attempt.min(2)))
+            },
+            LinearRetryStrategy::new().max_attempts(10),
+        )
+        .inspect(|_| Decision::RetryNow)
+        .stall_detector(1, |previous, next| previous == next)
+        .await
+        .unwrap_err();
+        assert_eq!(StopReason::Stalled, error.reason());
+        assert_eq!(3, error.len());
+        let Some(RetryPolicy::Retry(Some(last))) = error.errors_mut().last_mut() else {
+            unreachable!()
+        };
+        assert_eq!(Some(&mut Cell::new(2)), last.retried_ok_mut::<Cell<u8>>());
+
+        let mut error = RetryFuture::new(
+            || err::<(), _>(RetryPolicy::Fail(Cell::new(5u8))),
+            LinearRetryStrategy::new().max_attempts(1),
+        )
+        .inspect(|_| Decision::RetryNow)
+        .await
+        .unwrap_err();
+        let Some(RetryPolicy::Retry(Some(last))) = error.errors_mut().last_mut() else {
+            unreachable!()
+        };
+        assert_eq!(Some(&mut Cell::new(5)), last.retried_fail_mut::<Cell<u8>>());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_inspect_stop() {
+        let mut results = vec![Err(RetryPolicy::Retry(None)), Err(RetryPolicy::Retry(None))];
+        let error = RetryFuture::new(
+            move || std::future::ready(results.pop().unwrap()),
+            LinearRetryStrategy::new(),
+        )
+        .inspect(|_: &Result<(), RetryPolicy<()>>| Decision::Stop)
+        .await
+        .unwrap_err();
+        assert_eq!(StopReason::Inspected, error.reason());
+        assert_eq!(1, error.len());
+
+        let error = RetryFuture::new(
+            || std::future::ready(Err::<(), _>(RetryPolicy::Fail("fatal"))),
+            LinearRetryStrategy::new(),
+        )
+        .inspect(|_| Decision::Stop)
+        .await
+        .unwrap_err();
+        assert_eq!(StopReason::Fail, error.reason());
+
+        let value = RetryFuture::new(
+            || std::future::ready(Ok::<_, RetryPolicy<()>>(5)),
+            LinearRetryStrategy::new(),
+        )
+        .inspect(|_| Decision::Stop)
+        .await
+        .unwrap();
+        assert_eq!(5, value);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_drain_errors() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let (future, handle) = RetryFuture::new(
+            {
+                let attempts = attempts.clone();
+                move || match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 => err(RetryPolicy::<()>::Retry(Some(Error::msg("first")))),
+                    1 => err(RetryPolicy::Retry(Some(Error::msg("second")))),
+                    _ => ok(()),
+                }
+            },
+            LinearRetryStrategy::new().delay_between_retries(Duration::from_secs(1)),
+        )
+        .with_handle();
+        let task = tokio::spawn(future);
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let drained = handle.drain_errors();
+        assert_eq!(1, drained.len());
+        assert_eq!("Retry(first)", drained[0].summary());
+        assert!(handle.drain_errors().is_empty());
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(2, attempts.load(Ordering::SeqCst));
+        let drained = handle.drain_errors();
+        assert_eq!(1, drained.len());
+        assert_eq!("Retry(second)", drained[0].summary());
+
+        task.await.unwrap().unwrap();
+        assert!(handle.drain_errors().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_before_retry() {
+        let connected = Arc::new(AtomicBool::new(false));
+        let cleanups = Arc::new(AtomicUsize::new(0));
+        let start = tokio::time::Instant::now();
+        let value = RetryFuture::new(
+            {
+                let connected = connected.clone();
+                move || match connected.load(Ordering::SeqCst) {
+                    true => ok(5),
+                    false => err(RetryPolicy::<()>::Retry(None)),
+                }
+            },
+            LinearRetryStrategy::new().delay_between_retries(Duration::from_secs(1)),
+        )
+        .before_retry({
+            let cleanups = cleanups.clone();
+            move || {
+                let connected = connected.clone();
+                let cleanups = cleanups.clone();
+                async move {
+                    // the cleanup runs before the delay of the strategy
+                    assert_eq!(Duration::ZERO, start.elapsed());
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                    cleanups.fetch_add(1, Ordering::SeqCst);
+                    connected.store(true, Ordering::SeqCst);
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(5, value);
+        assert_eq!(1, cleanups.load(Ordering::SeqCst));
+        assert_eq!(Duration::from_secs(3), start.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stall_detector() {
+        #[derive(Debug, PartialEq)]
+        struct JobStatus(&'static str);
+
+        let mut statuses = vec!["queued", "running", "running", "running", "running"].into_iter();
+        let error = RetryFuture::new(
+            move || ok::<_, RetryPolicy<()>>(JobStatus(statuses.next().unwrap_or("running"))),
+            InfiniteRetryStrategy::new(),
+        )
+        .inspect(|status| match status {
+            Ok(JobStatus("done")) => Decision::Continue,
+            _ => Decision::RetryNow,
+        })
+        .stall_detector(2, |previous, next| previous == next)
+        .compact_errors()
+        .await
+        .unwrap_err();
+        assert_eq!(StopReason::Stalled, error.reason());
+        // "running" did not change after the second and the third attempts
+        assert_eq!(4, error.len());
+        assert_eq!(Some(&JobStatus("running")), error.last_ok::<JobStatus>());
+
+        // errors other than retried values are never compared
+        let error = RetryFuture::new(
+            || err::<(), _>(RetryPolicy::<()>::Retry(Some(Error::msg("not a status")))),
+            LinearRetryStrategy::new().max_attempts(3),
+        )
+        .stall_detector(1, |_, _| true)
+        .await
+        .unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+    }
+
+    #[tokio::test]
+    async fn test_zero_delay_yields() {
+        let attempt = AtomicUsize::new(0);
+        let mut f = std::pin::pin!(RetryFuture::new(
+            || {
+                attempt.fetch_add(1, Ordering::SeqCst);
+                err::<(), _>(RetryPolicy::<()>::Retry(None))
+            },
+            LinearRetryStrategy::new().max_attempts(2).delay_between_retries(Duration::ZERO),
+        ));
+        // every poll runs no more than one attempt
+        assert!(futures::poll!(f.as_mut()).is_pending());
+        assert_eq!(1, attempt.load(Ordering::SeqCst));
+        assert!(futures::poll!(f.as_mut()).is_pending());
+        assert_eq!(2, attempt.load(Ordering::SeqCst));
+        assert_eq!(3, f.await.unwrap_err().len());
+        assert_eq!(3, attempt.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_after() {
+        let mut attempt = 0;
+        let f = RetryFuture::new(
+            || {
+                attempt += 1;
+                let result = if attempt == 1 {
+                    Err(RetryPolicy::<()>::Retry(Some(
+                        Error::msg("overloaded").retry_after(Duration::from_secs(3)),
+                    )))
+                } else {
+                    Ok(())
+                };
+                async move { result }
+            },
+            MyRetryStrategy { max_attempts: 5, counter: vec![] },
+        );
+        let started = tokio::time::Instant::now();
+        f.await.unwrap();
+        assert_eq!(Duration::from_secs(3), started.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_delay_factor() {
+        let mut attempt = 0;
+        let f = RetryFuture::new(
+            || {
+                attempt += 1;
+                let result = match attempt {
+                    1 => Err(RetryPolicy::<()>::Retry(Some(Error::msg("busy").delay_factor(2.0)))),
+                    2 => Err(RetryPolicy::Retry(None)),
+                    _ => Ok(()),
+                };
+                async move { result }
+            },
+            LinearRetryStrategy::new().delay_between_retries(Duration::from_secs(1)),
+        );
+        let started = tokio::time::Instant::now();
+        f.await.unwrap();
+        // the busy server doubles only the delay after its error
+        assert_eq!(Duration::from_secs(3), started.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_strategy_macro() {
+        retry_strategy!(Doubling, max: 3, |n| Duration::from_millis(100 << n));
+
+        let started = tokio::time::Instant::now();
+        let error = RetryFuture::new(|| err::<(), _>(RetryPolicy::<()>::Retry(None)), Doubling)
+            .await
+            .unwrap_err();
+        assert_eq!(4, error.len());
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        assert_eq!(Duration::from_millis(700), started.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_attempt_timeout_from_strategy() {
+        retry_strategy!(Doubling, max: 5, |n| Duration::from_millis(100 << n));
+
+        let mut attempt = 0;
+        let f = RetryFuture::new(
+            || {
+                attempt += 1;
+                async {
+                    tokio::time::sleep(Duration::from_millis(250)).await;
+                    Ok::<_, RetryPolicy>(())
+                }
+            },
+            Doubling,
+        )
+        .attempt_timeout_from_strategy()
+        .record_timeline();
+        let timeline = f.timeline().unwrap();
+        let started = tokio::time::Instant::now();
+        f.await.unwrap();
+        // attempts time out after 100ms and 200ms, the third one has 400ms to finish
+        assert_eq!(Duration::from_millis(100 + 100 + 200 + 200 + 250), started.elapsed());
+        assert_eq!(3, attempt);
+        let records = timeline.records();
+        assert_eq!(2, records.len());
+        assert_eq!(Some(Duration::from_millis(200)), records[1].delay);
+        assert_eq!("Retry(attempt timed out after 200ms)", records[1].error);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_attempt_timeout_keeps_strategy_state() {
+        struct Counting(Arc<AtomicUsize>);
+
+        impl RetryStrategy for Counting {
+            fn check_attempt(&mut self, _attempts_before: usize) -> Result<Duration, RetryStop> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Ok(Duration::from_secs(1))
+            }
+
+            fn retry_early_returned_errors(&self) -> bool {
+                true
+            }
+
+            fn peek_next_delay(&self, _attempts_before: usize) -> Option<Duration> {
+                Some(Duration::from_secs(1))
+            }
+        }
+
+        let checks = Arc::new(AtomicUsize::new(0));
+        let mut results = vec![ok(()), err(RetryPolicy::<()>::Retry(None))];
+        RetryFuture::new(move || results.pop().unwrap(), Counting(checks.clone()))
+            .attempt_timeout_from_strategy()
+            .await
+            .unwrap();
+        // only the failed attempt asked the strategy
+        assert_eq!(1, checks.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_debug() {
+        let policy = RetryPolicy::<()>::from(std::io::Error::other("early"));
+        let debug = format!("{policy:?}");
+        assert!(debug.starts_with("Retry { early_returned: true, error: "), "{debug}");
+        assert!(debug.contains("early"), "{debug}");
+
+        let policy = RetryPolicy::<()>::Retry(Some(Error::msg("explicit")));
+        assert!(format!("{policy:?}").starts_with("Retry { early_returned: false, error: "));
+
+        assert_eq!("Retry(None)", format!("{:?}", RetryPolicy::<()>::Retry(None)));
+        assert_eq!("Fail(\"fatal\")", format!("{:?}", RetryPolicy::Fail("fatal")));
+    }
+
+    #[tokio::test]
+    async fn test_from_io_not_found() {
+        let mut retry_strategy = MyRetryStrategy { max_attempts: 5, counter: vec![] };
+        let f = RetryFuture::new(
+            || async {
+                tokio::fs::read("/nonexistent/retry_future").await.map_err(RetryPolicy::from_io)
+            },
+            &mut retry_strategy,
+        );
+        let error: std::io::Error = f.await.unwrap_err().into_fail().unwrap();
+        assert_eq!(std::io::ErrorKind::NotFound, error.kind());
+        assert_eq!(0, retry_strategy.counter.len())
+    }
+
+    #[tokio::test]
+    async fn test_failover() {
+        let mut primary_attempts = 0;
+        let mut secondary_attempts = 0;
+        let f = RetryFuture::new_failover(
+            || {
+                primary_attempts += 1;
+                err::<&str, RetryPolicy>(RetryPolicy::Retry(None))
+            },
+            || {
+                secondary_attempts += 1;
+                ok("secondary")
+            },
+            MyRetryStrategy { max_attempts: 5, counter: vec![] },
+        );
+        assert_eq!("secondary", f.await.unwrap());
+        assert_eq!(1, primary_attempts);
+        assert_eq!(1, secondary_attempts);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let mut retry_strategy = MyRetryStrategy { max_attempts: 5, counter: vec![] };
+        let f = RetryFuture::new(
+            || {
+                let shutdown = shutdown.clone();
+                async move {
+                    shutdown.store(true, Ordering::Relaxed);
+                    Err::<u8, RetryPolicy>(RetryPolicy::Retry(None))
+                }
+            },
+            &mut retry_strategy,
+        )
+        .with_shutdown(shutdown.clone());
+        let error = f.await.unwrap_err();
+        assert_eq!(StopReason::Cancelled, error.reason());
+        assert_eq!(1, error.len());
+        assert_eq!(0, retry_strategy.counter.len())
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_before_first_poll() {
+        let calls = &AtomicUsize::new(0);
+        let error = RetryFuture::new(
+            || async move {
+                calls.fetch_add(1, Ordering::Relaxed);
+                Ok::<u8, RetryPolicy>(1)
+            },
+            LinearRetryStrategy::new(),
+        )
+        .with_shutdown(Arc::new(AtomicBool::new(true)))
+        .await
+        .unwrap_err();
+        assert_eq!(StopReason::Cancelled, error.reason());
+        assert!(error.is_empty());
+        assert_eq!(0, calls.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_reason() {
+        let f = RetryFuture::new(
+            || err::<u8, _>(RetryPolicy::Fail("fail")),
+            MyRetryStrategy { max_attempts: 2, counter: vec![] },
+        );
+        assert_eq!(StopReason::Fail, f.await.unwrap_err().reason());
+
+        let f = RetryFuture::new(
+            || err::<u8, RetryPolicy>(RetryPolicy::Retry(None)),
+            MyRetryStrategy { max_attempts: 2, counter: vec![] },
+        );
+        assert_eq!(StopReason::TooManyAttempts, f.await.unwrap_err().reason());
+
+        let f = RetryFuture::new(
+            || async { Ok::<_, RetryPolicy>("non-integer".parse::<u32>()?) },
+            MyRetryStrategy { max_attempts: 2, counter: vec![] },
+        );
+        assert_eq!(StopReason::EarlyReturned, f.await.unwrap_err().reason());
+
+        let f = RetryFuture::new(
+            || err::<u8, RetryPolicy>(RetryPolicy::Retry(None)),
+            ExponentialRetryStrategy::new()
+                .initial_delay(Duration::from_millis(1))
+                .max_total_delay(Duration::from_millis(2)),
+        );
+        assert_eq!(StopReason::Budget, f.await.unwrap_err().reason());
+    }
+
+    #[tokio::test]
+    async fn test_timeline() {
+        let mut attempt = 0;
+        let f = RetryFuture::new(
+            || {
+                attempt += 1;
+                err::<u8, RetryPolicy>(RetryPolicy::Retry(Some(Error::msg(format!(
+                    "attempt {attempt}"
+                )))))
+            },
+            MyRetryStrategy { max_attempts: 2, counter: vec![] },
+        )
+        .record_timeline();
+        let timeline = f.timeline().unwrap();
+        let error = f.await.unwrap_err();
+        let expected = vec![
+            AttemptRecord {
+                attempt: 0,
+                delay: Some(Duration::from_millis(1)),
+                error: String::from("Retry(attempt 1)"),
+            },
+            AttemptRecord {
+                attempt: 1,
+                delay: Some(Duration::from_millis(1)),
+                error: String::from("Retry(attempt 2)"),
+            },
+            AttemptRecord { attempt: 2, delay: None, error: String::from("Retry(attempt 3)") },
+        ];
+        assert_eq!(expected, error.timeline());
+        assert_eq!(expected, timeline.records());
+    }
+
+    #[tokio::test]
+    async fn test_timeline_success() {
+        let mut attempt = 0;
+        let f = RetryFuture::new(
+            || {
+                attempt += 1;
+                if attempt == 1 {
+                    err(RetryPolicy::Retry(None))
+                } else {
+                    ok::<_, RetryPolicy>(attempt)
+                }
+            },
+            MyRetryStrategy { max_attempts: 2, counter: vec![] },
+        );
+        assert!(f.timeline().is_none());
+        let f = f.record_timeline();
+        let timeline = f.timeline().unwrap();
+        assert_eq!(2, f.await.unwrap());
+        assert_eq!(
+            vec![AttemptRecord {
+                attempt: 0,
+                delay: Some(Duration::from_millis(1)),
+                error: String::from("Retry(None)"),
+            }],
+            timeline.records()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_propagate_policy() {
+        fn helper() -> Result<u8, RetryPolicy<&'static str>> {
+            Err(RetryPolicy::Fail("helper"))
+        }
+
+        let mut retry_strategy = MyRetryStrategy { max_attempts: 5, counter: vec![] };
+        let f = RetryFuture::new(
+            || async { Ok::<_, RetryPolicy<_>>(helper()? + 1) },
+            &mut retry_strategy,
+        );
+        let error = f.await.unwrap_err();
+        assert_eq!(StopReason::Fail, error.reason());
+        assert_eq!(Some("helper"), error.into_fail());
+        assert_eq!(0, retry_strategy.counter.len())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hedged() {
+        let mut attempt = 0;
+        let f = RetryFuture::new(
+            || {
+                attempt += 1;
+                let attempt = attempt;
+                async move {
+                    if attempt == 1 {
+                        tokio::time::sleep(Duration::from_secs(10)).await;
+                    }
+                    Ok::<_, RetryPolicy>(attempt)
+                }
+            },
+            MyRetryStrategy { max_attempts: 5, counter: vec![] },
+        )
+        .hedged(Duration::from_millis(100), 2);
+        let started = tokio::time::Instant::now();
+        assert_eq!(2, f.await.unwrap());
+        assert_eq!(Duration::from_millis(100), started.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_hedged_max_in_flight() {
+        let mut attempt = 0;
+        let mut retry_strategy = MyRetryStrategy { max_attempts: 5, counter: vec![] };
+        let f = RetryFuture::new(
+            || {
+                attempt += 1;
+                let attempt = attempt;
+                async move {
+                    if attempt == 1 {
+                        tokio::time::sleep(Duration::from_millis(500)).await;
+                        Err(RetryPolicy::Retry(None))
+                    } else {
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        Ok::<_, RetryPolicy>(attempt)
+                    }
+                }
+            },
+            &mut retry_strategy,
+        )
+        .hedged(Duration::from_millis(100), 2);
+        // attempts are started at 0ms and 100ms, then no more than 2 are in flight,
+        // so the third one is started only after the first one fails at 500ms
+        let started = tokio::time::Instant::now();
+        assert_eq!(2, f.await.unwrap());
+        assert_eq!(Duration::from_millis(1100), started.elapsed());
+        assert_eq!(3, attempt);
+        assert_eq!(1, retry_strategy.counter.len());
+    }
+
+    #[tokio::test]
+    async fn test_before_attempt() {
+        let mut retry_strategy = MyRetryStrategy { max_attempts: 5, counter: vec![] };
+        let mut attempt = 0;
+        let mut checks = 0;
+        let f = RetryFuture::new(
+            || {
+                attempt += 1;
+                err::<(), _>(RetryPolicy::Retry(None))
+            },
+            &mut retry_strategy,
+        )
+        .before_attempt(move || {
+            checks += 1;
+            if checks == 2 {
+                Err("unhealthy")
+            } else {
+                Ok(())
+            }
+        });
+        let error = f.await.unwrap_err();
+        assert_eq!(2, attempt);
+        assert_eq!(StopReason::Fail, error.reason());
+        assert!(matches!(error.last_error(), Some(RetryPolicy::Fail("unhealthy"))));
+        assert_eq!(3, error.len());
+    }
+
+    #[tokio::test]
+    async fn test_compact_errors() {
+        #[derive(Debug)]
+        struct HeavyError(Arc<Vec<u8>>);
+
+        impl std::fmt::Display for HeavyError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "heavy error of {} bytes", self.0.len())
+            }
+        }
+
+        impl std::error::Error for HeavyError {}
+
+        let payload = Arc::new(vec![0u8; 1024 * 1024]);
+        let f = RetryFuture::new(
+            || {
+                let payload = payload.clone();
+                async move {
+                    let e = anyhow::Error::new(HeavyError(payload)).context("request failed");
+                    Err::<(), RetryPolicy>(RetryPolicy::Retry(Some(Error::from_anyhow(e, false))))
+                }
+            },
+            MyRetryStrategy { max_attempts: 3, counter: vec![] },
+        )
+        .compact_errors();
+        let error = f.await.unwrap_err();
+        assert_eq!(4, error.len());
+        // no error keeps the payload alive
+        assert_eq!(1, Arc::strong_count(&payload));
+        for e in error.errors() {
+            let RetryPolicy::Retry(Some(e)) = e else { panic!("unexpected error {e:?}") };
+            assert_eq!(1, e.error.chain().count());
+            assert_eq!(
+                Some("request failed: heavy error of 1048576 bytes"),
+                e.error.downcast_ref::<String>().map(String::as_str)
+            );
+        }
+    }
 }
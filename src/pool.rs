@@ -0,0 +1,109 @@
+use crate::clock::Clock;
+use crate::future::RetryFuture;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Bounds the number of attempts running at once across many [RetryFutures](RetryFuture),
+/// e.g. so that retries during an outage of a downstream service do not all fire together.
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::{LinearRetryStrategy, RetryFuture, RetryPolicy, RetryPool};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let pool = RetryPool::new(2);
+/// let futures = (0..10).map(|i| {
+///     pool.limit(RetryFuture::new(
+///         move || async move { Ok::<_, RetryPolicy>(i) },
+///         LinearRetryStrategy::new(),
+///     ))
+/// });
+/// let results = futures::future::join_all(futures).await;
+/// assert_eq!(10, results.len());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPool {
+    semaphore: Arc<Semaphore>,
+}
+
+impl RetryPool {
+    /// Creates a pool running no more than `max_concurrent_attempts` attempts at once
+    pub fn new(max_concurrent_attempts: usize) -> Self {
+        Self { semaphore: Arc::new(Semaphore::new(max_concurrent_attempts)) }
+    }
+
+    /// Makes every attempt of `future` wait for a free slot in the pool,
+    /// see [RetryFuture::with_permit]
+    pub fn limit<F, Fut, RS, E, C: Clock>(
+        &self,
+        future: RetryFuture<F, Fut, RS, E, C>,
+    ) -> RetryFuture<F, Fut, RS, E, C> {
+        future.with_permit(self.semaphore.clone())
+    }
+
+    /// Semaphore behind the pool, e.g. to [close](Semaphore::close) it
+    pub fn semaphore(&self) -> &Arc<Semaphore> {
+        &self.semaphore
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LinearRetryStrategy, RetryPolicy, StopReason};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test(start_paused = true)]
+    async fn bounded_concurrency() {
+        let pool = RetryPool::new(2);
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let futures = (0..5).map(|_| {
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            let mut attempt = 0;
+            pool.limit(RetryFuture::new(
+                move || {
+                    attempt += 1;
+                    let attempt = attempt;
+                    let in_flight = in_flight.clone();
+                    let max_in_flight = max_in_flight.clone();
+                    async move {
+                        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_in_flight.fetch_max(now, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                        if attempt == 1 {
+                            Err(RetryPolicy::<()>::Retry(None))
+                        } else {
+                            Ok(())
+                        }
+                    }
+                },
+                LinearRetryStrategy::new().delay_between_retries(Duration::from_millis(1)),
+            ))
+        });
+        let started = tokio::time::Instant::now();
+        for result in futures::future::join_all(futures).await {
+            result.unwrap();
+        }
+        assert_eq!(2, max_in_flight.load(Ordering::SeqCst));
+        // 10 attempts of 10ms, 2 at once
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn closed() {
+        let pool = RetryPool::new(1);
+        pool.semaphore().close();
+        let f = pool.limit(RetryFuture::new(
+            || async { Ok::<_, RetryPolicy>(()) },
+            LinearRetryStrategy::new(),
+        ));
+        assert_eq!(StopReason::Cancelled, f.await.unwrap_err().reason());
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{Error, RetryPolicy};
+
+/// Classification of an error type into [RetryPolicy], implemented once
+/// instead of matching errors inside every [future](crate::FutureFactory::Future).
+///
+/// A blanket `From<E: Retriable>` for [RetryPolicy] would conflict with the
+/// `From<T: Into<anyhow::Error>>` one, which makes `?` retry any error. So results are
+/// converted explicitly with [into_policy](RetriableExt::into_policy) before `?`.
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::{Retriable, RetriableExt, RetryPolicy};
+///
+/// #[derive(Debug)]
+/// enum ApiError {
+///     Timeout,
+///     Unauthorized,
+/// }
+///
+/// impl Retriable for ApiError {
+///     fn retry_policy(self) -> RetryPolicy<Self> {
+///         match self {
+///             ApiError::Timeout => RetryPolicy::Retry(None),
+///             ApiError::Unauthorized => RetryPolicy::Fail(self),
+///         }
+///     }
+/// }
+///
+/// fn call(e: ApiError) -> Result<(), RetryPolicy<ApiError>> {
+///     Err(e).into_policy()?;
+///     Ok(())
+/// }
+///
+/// assert!(matches!(call(ApiError::Timeout), Err(RetryPolicy::Retry(None))));
+/// assert!(matches!(call(ApiError::Unauthorized), Err(RetryPolicy::Fail(ApiError::Unauthorized))));
+/// ```
+pub trait Retriable: Sized {
+    fn retry_policy(self) -> RetryPolicy<Self>;
+}
+
+/// Converts errors of `Result` to [RetryPolicy] using [Retriable]
+pub trait RetriableExt<T, E> {
+    fn into_policy(self) -> Result<T, RetryPolicy<E>>;
+}
+
+impl<T, E: Retriable> RetriableExt<T, E> for Result<T, E> {
+    fn into_policy(self) -> Result<T, RetryPolicy<E>> {
+        self.map_err(Retriable::retry_policy)
+    }
+}
+
+/// Converts errors of `Result` to [RetryPolicy] when all of them are either
+/// retryable or fatal
+///
+/// ```rust
+/// use retry_future::{ResultExt, RetryPolicy};
+///
+/// let retry: Result<u32, RetryPolicy> = "x".parse::<u32>().retryable();
+/// assert!(matches!(retry, Err(RetryPolicy::Retry(Some(_)))));
+///
+/// let fail = Err::<u32, _>("invalid").fatal();
+/// assert!(matches!(fail, Err(RetryPolicy::Fail("invalid"))));
+/// ```
+pub trait ResultExt<T, E> {
+    /// Maps the error into [RetryPolicy::Retry] keeping it as [Error](crate::Error)
+    fn retryable<F>(self) -> Result<T, RetryPolicy<F>>
+    where
+        E: Into<anyhow::Error>;
+
+    /// Maps the error into [RetryPolicy::Fail]
+    fn fatal(self) -> Result<T, RetryPolicy<E>>;
+}
+
+impl<T, E> ResultExt<T, E> for Result<T, E> {
+    fn retryable<F>(self) -> Result<T, RetryPolicy<F>>
+    where
+        E: Into<anyhow::Error>,
+    {
+        self.map_err(|e| RetryPolicy::Retry(Some(Error::from_anyhow(e.into(), false))))
+    }
+
+    fn fatal(self) -> Result<T, RetryPolicy<E>> {
+        self.map_err(RetryPolicy::Fail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LinearRetryStrategy, RetryFuture, StopReason};
+    use std::time::Duration;
+
+    #[derive(Debug, PartialEq)]
+    enum DbError {
+        Deadlock,
+        Disconnected(&'static str),
+        UniqueViolation,
+    }
+
+    impl Retriable for DbError {
+        fn retry_policy(self) -> RetryPolicy<Self> {
+            match self {
+                DbError::Deadlock => RetryPolicy::Retry(None),
+                DbError::Disconnected(reason) => RetryPolicy::Retry(Some(Error::msg(reason))),
+                DbError::UniqueViolation => RetryPolicy::Fail(self),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn classify_with_question_mark() {
+        let mut errors =
+            vec![DbError::UniqueViolation, DbError::Disconnected("reset"), DbError::Deadlock];
+        let f = RetryFuture::new(
+            || {
+                let result = errors.pop().map_or(Ok(()), Err);
+                async move {
+                    result.into_policy()?;
+                    Ok(())
+                }
+            },
+            LinearRetryStrategy::new().delay_between_retries(Duration::ZERO),
+        );
+        let error = f.await.unwrap_err();
+        assert_eq!(StopReason::Fail, error.reason());
+        assert_eq!(3, error.len());
+        assert!(matches!(error.errors()[0], RetryPolicy::Retry(None)));
+        assert!(
+            matches!(&error.errors()[1], RetryPolicy::Retry(Some(e)) if e.error.to_string() == "reset")
+        );
+        assert_eq!(Some(DbError::UniqueViolation), error.into_fail());
+    }
+
+    #[test]
+    fn retryable() {
+        let result: Result<u32, RetryPolicy<()>> = "x".parse::<u32>().retryable();
+        let Err(RetryPolicy::Retry(Some(e))) = result else { panic!("must be retried") };
+        assert!(!e.is_early_returned);
+        assert!(e.error.downcast_ref::<std::num::ParseIntError>().is_some());
+    }
+
+    #[test]
+    fn fatal() {
+        let result = Err::<u32, _>(DbError::UniqueViolation).fatal();
+        assert!(matches!(result, Err(RetryPolicy::Fail(DbError::UniqueViolation))));
+    }
+
+    #[test]
+    fn ok_pass_through() {
+        assert_eq!(Some(5), "5".parse::<u32>().retryable::<()>().ok());
+        assert_eq!(Some(5), Ok::<_, DbError>(5).fatal().ok());
+    }
+}
@@ -1,38 +1,575 @@
+pub mod asynchronous;
 pub mod exponential;
+pub mod function;
+mod global;
+pub mod growing_linear;
 pub mod infinite;
+pub mod iterator;
 pub mod linear;
+#[cfg(feature = "log")]
+pub mod logged;
+pub mod no_retry;
+pub mod one_based;
+pub mod planner;
+pub mod poll;
+pub mod quantized;
+pub mod rate_limited;
+pub mod round_robin;
+pub mod round_to_millis;
+pub mod shared;
+pub mod sliding_window;
 
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use std::time::Duration;
 
-use crate::error::TooManyAttempts;
+use crate::error::{RetryStop, StrategyConfigError};
+pub use asynchronous::{AsyncDecision, AsyncRetryStrategy, AsyncStrategy};
 pub use exponential::ExponentialRetryStrategy;
+pub use function::FnStrategy;
+pub use global::{default_strategy, set_default_strategy};
+pub use growing_linear::GrowingLinearStrategy;
 pub use infinite::InfiniteRetryStrategy;
+pub use iterator::IteratorStrategy;
 pub use linear::LinearRetryStrategy;
+#[cfg(feature = "log")]
+pub use logged::Logged;
+pub use no_retry::NoRetryStrategy;
+pub use one_based::OneBased;
+pub use planner::Planner;
+pub use poll::PollStrategy;
+pub use quantized::Quantized;
+pub use rate_limited::RateLimitedStrategy;
+pub use round_robin::RoundRobinStrategy;
+pub use round_to_millis::RoundToMillis;
+pub use shared::SharedStrategy;
+pub use sliding_window::SlidingWindowStrategy;
 
 /// Configuration trait for [RetryFuture](crate::RetryFuture).
 ///
 /// Goal of the trait is to return either a [duration](std::time::Duration)
 /// which means how long a future needs to sleep before trying to resolve again
-/// or an [error](TooManyAttempts) if there were already too many attempts.
+/// or an [error](RetryStop) if there must be no more attempts, e.g. there were
+/// already too many of them.
+///
+/// ## Counting attempts
+///
+/// `attempts_before` passed to [check_attempt](RetryStrategy::check_attempt) is the number
+/// of attempts which failed before the one that has just failed:
+///
+/// | Failed attempt  | `attempts_before` |
+/// |-----------------|-------------------|
+/// | first           | 0                 |
+/// | first retry     | 1                 |
+/// | `n`-th retry    | `n`               |
+///
+/// So built-in strategies with `max_attempts(n)` stop once `attempts_before >= n`, which
+/// allows `n` retries and `n + 1` attempts in total. Wrap a strategy into [OneBased]
+/// if it expects the number of the failed attempt instead.
 pub trait RetryStrategy {
     /// `attempts_before` means how many attempts a [future](crate::future::FutureFactory::Future)
     /// was trying to resolve to `Ok(_)` after returning `Err(_)`.
-    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, TooManyAttempts>;
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop>;
+
+    /// If it returns a decision, [RetryFuture](crate::RetryFuture) and
+    /// [retry_items](crate::RetryStreamExt::retry_items) await it instead of
+    /// calling [check_attempt](RetryStrategy::check_attempt), see [AsyncRetryStrategy].
+    fn check_attempt_async(&mut self, _attempts_before: usize) -> Option<AsyncDecision> {
+        None
+    }
 
     /// If `true`, errors propagated using `?` inside a [future](crate::future::FutureFactory::Future)
     /// will be retried.
     fn retry_early_returned_errors(&self) -> bool;
+
+    /// Called by [RetryFuture](crate::RetryFuture) after every attempt with `true`
+    /// if the [future](crate::future::FutureFactory::Future) resolved to `Ok(_)`.
+    ///
+    /// Unlike `attempts_before`, which starts from zero in every [RetryFuture](crate::RetryFuture),
+    /// outcomes can be accumulated by a strategy shared between several futures.
+    fn record_outcome(&mut self, _success: bool) {}
+
+    /// Delay which [check_attempt](RetryStrategy::check_attempt) would return
+    /// for `attempts_before` without changing the strategy, e.g. to show a countdown
+    /// before the next attempt.
+    ///
+    /// `None` means that there would be no more attempts or the delay cannot be
+    /// known in advance, e.g. because it is random. Only strategies without state
+    /// implement it.
+    fn peek_next_delay(&self, _attempts_before: usize) -> Option<Duration> {
+        None
+    }
+
+    /// Stable name of the strategy used in logs
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+
+    /// Checks that the configuration makes sense, e.g. that there is at least one retry.
+    /// Called by [RetryFuture::try_new](crate::RetryFuture::try_new).
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        Ok(())
+    }
+
+    /// Wraps the strategy into [Logged], which logs every decision
+    #[cfg(feature = "log")]
+    fn logged(self) -> Logged<Self>
+    where
+        Self: Sized,
+    {
+        Logged(self)
+    }
+
+    /// Turns the configured strategy into a [Planner], which creates a fresh instance
+    /// of it for every [RetryFuture](crate::RetryFuture)
+    fn into_planner(self) -> Planner<Self>
+    where
+        Self: Sized + Clone,
+    {
+        Planner::from(self)
+    }
+
+    /// Wraps the strategy into [OneBased], which counts attempts from 1
+    fn one_based(self) -> OneBased<Self>
+    where
+        Self: Sized,
+    {
+        OneBased(self)
+    }
+
+    /// Wraps the strategy into [RoundToMillis], which rounds delays to whole milliseconds
+    fn round_to_millis(self) -> RoundToMillis<Self>
+    where
+        Self: Sized,
+    {
+        RoundToMillis(self)
+    }
+
+    /// Wraps the strategy into [Quantized], which rounds delays to the nearest multiple
+    /// of `quantum`
+    fn quantize(self, quantum: Duration) -> Quantized<Self>
+    where
+        Self: Sized,
+    {
+        Quantized::new(self, quantum)
+    }
+}
+
+/// Multiplies `duration` by `factor`, returning [Duration::MAX] on overflow.
+///
+/// Useful for custom [retry strategies](RetryStrategy) where the factor grows quickly,
+/// e.g. exponentially.
+///
+/// ```rust
+/// use retry_future::saturating_mul;
+/// use std::time::Duration;
+///
+/// assert_eq!(saturating_mul(Duration::from_secs(2), 3), Duration::from_secs(6));
+/// assert_eq!(saturating_mul(Duration::from_secs(2), u64::MAX), Duration::MAX);
+/// ```
+pub fn saturating_mul(duration: Duration, factor: u64) -> Duration {
+    const NANOS_PER_SEC: u128 = 1_000_000_000;
+    let nanos = duration.as_nanos().saturating_mul(factor as u128);
+    match u64::try_from(nanos / NANOS_PER_SEC) {
+        Ok(secs) => Duration::new(secs, (nanos % NANOS_PER_SEC) as u32),
+        Err(_) => Duration::MAX,
+    }
 }
 
 impl<T> RetryStrategy for &mut T
 where
     T: RetryStrategy,
 {
-    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, TooManyAttempts> {
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
         (*self).check_attempt(attempts_before)
     }
 
+    fn check_attempt_async(&mut self, attempts_before: usize) -> Option<AsyncDecision> {
+        (*self).check_attempt_async(attempts_before)
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        (**self).retry_early_returned_errors()
+    }
+
+    fn record_outcome(&mut self, success: bool) {
+        (*self).record_outcome(success)
+    }
+
+    fn peek_next_delay(&self, attempts_before: usize) -> Option<Duration> {
+        (**self).peek_next_delay(attempts_before)
+    }
+
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        (**self).validate()
+    }
+}
+
+impl<T> RetryStrategy for Box<T>
+where
+    T: RetryStrategy + ?Sized,
+{
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        (**self).check_attempt(attempts_before)
+    }
+
+    fn check_attempt_async(&mut self, attempts_before: usize) -> Option<AsyncDecision> {
+        (**self).check_attempt_async(attempts_before)
+    }
+
     fn retry_early_returned_errors(&self) -> bool {
         (**self).retry_early_returned_errors()
     }
+
+    fn record_outcome(&mut self, success: bool) {
+        (**self).record_outcome(success)
+    }
+
+    fn peek_next_delay(&self, attempts_before: usize) -> Option<Duration> {
+        (**self).peek_next_delay(attempts_before)
+    }
+
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        (**self).validate()
+    }
+}
+
+/// Allows to share a strategy between futures on a single thread,
+/// e.g. inside tokio `LocalSet`
+impl<T> RetryStrategy for Rc<RefCell<T>>
+where
+    T: RetryStrategy,
+{
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        self.borrow_mut().check_attempt(attempts_before)
+    }
+
+    fn check_attempt_async(&mut self, attempts_before: usize) -> Option<AsyncDecision> {
+        self.borrow_mut().check_attempt_async(attempts_before)
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        self.borrow().retry_early_returned_errors()
+    }
+
+    fn record_outcome(&mut self, success: bool) {
+        self.borrow_mut().record_outcome(success)
+    }
+
+    fn peek_next_delay(&self, attempts_before: usize) -> Option<Duration> {
+        self.borrow().peek_next_delay(attempts_before)
+    }
+
+    fn name(&self) -> &'static str {
+        self.borrow().name()
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        self.borrow().validate()
+    }
+}
+
+/// Allows to share a strategy between futures running concurrently on several threads
+impl<T> RetryStrategy for Arc<Mutex<T>>
+where
+    T: RetryStrategy,
+{
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        lock(self).check_attempt(attempts_before)
+    }
+
+    fn check_attempt_async(&mut self, attempts_before: usize) -> Option<AsyncDecision> {
+        lock(self).check_attempt_async(attempts_before)
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        lock(self).retry_early_returned_errors()
+    }
+
+    fn record_outcome(&mut self, success: bool) {
+        lock(self).record_outcome(success)
+    }
+
+    fn peek_next_delay(&self, attempts_before: usize) -> Option<Duration> {
+        lock(self).peek_next_delay(attempts_before)
+    }
+
+    fn name(&self) -> &'static str {
+        lock(self).name()
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        lock(self).validate()
+    }
+}
+
+/// Most strategies would never retry without attempts
+fn validate_max_attempts(
+    strategy: &'static str,
+    max_attempts: usize,
+) -> Result<(), StrategyConfigError> {
+    if max_attempts == 0 {
+        return Err(StrategyConfigError::new(strategy, "max attempts must be positive"));
+    }
+    Ok(())
+}
+
+/// A strategy cannot be left in an inconsistent state by a panic, so poisoning is ignored
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RetryFuture, RetryPolicy};
+
+    #[test]
+    fn check_saturating_mul() {
+        assert_eq!(saturating_mul(Duration::from_millis(1500), 0), Duration::ZERO);
+        assert_eq!(saturating_mul(Duration::from_millis(1500), 3), Duration::from_millis(4500));
+        assert_eq!(
+            saturating_mul(Duration::from_secs(1), u32::MAX as u64),
+            Duration::from_secs(u32::MAX as u64)
+        );
+        assert_eq!(
+            saturating_mul(Duration::from_nanos(1), u32::MAX as u64 + 1),
+            Duration::from_nanos(u32::MAX as u64 + 1)
+        );
+        assert_eq!(
+            saturating_mul(Duration::from_secs(u32::MAX as u64), u32::MAX as u64),
+            Duration::from_secs(u32::MAX as u64 * u32::MAX as u64)
+        );
+        assert_eq!(
+            saturating_mul(Duration::from_secs(u32::MAX as u64 + 2), u32::MAX as u64 + 2),
+            Duration::MAX
+        );
+        assert_eq!(saturating_mul(Duration::MAX, 1), Duration::MAX);
+        assert_eq!(saturating_mul(Duration::MAX, 2), Duration::MAX);
+    }
+
+    /// Records every `attempts_before` and stops once it reaches `max`
+    struct Recorder {
+        inputs: Arc<Mutex<Vec<usize>>>,
+        max: usize,
+    }
+
+    impl RetryStrategy for Recorder {
+        fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+            self.inputs.lock().unwrap().push(attempts_before);
+            if attempts_before >= self.max {
+                return Err(RetryStop::MaxAttempts);
+            }
+            Ok(Duration::ZERO)
+        }
+
+        fn retry_early_returned_errors(&self) -> bool {
+            true
+        }
+    }
+
+    /// Inputs of `check_attempt` and the number of attempts made
+    async fn check_attempt_inputs(strategy: impl RetryStrategy) -> usize {
+        let mut attempts = 0;
+        RetryFuture::new(
+            || {
+                attempts += 1;
+                async { Err::<(), _>(RetryPolicy::<()>::Retry(None)) }
+            },
+            strategy,
+        )
+        .await
+        .unwrap_err();
+        attempts
+    }
+
+    #[tokio::test]
+    async fn check_counting() {
+        let inputs = Arc::new(Mutex::new(Vec::new()));
+        let attempts = check_attempt_inputs(Recorder { inputs: inputs.clone(), max: 3 }).await;
+        assert_eq!(vec![0, 1, 2, 3], std::mem::take(&mut *inputs.lock().unwrap()));
+        assert_eq!(4, attempts);
+
+        let strategy = Recorder { inputs: inputs.clone(), max: 3 }.one_based();
+        let attempts = check_attempt_inputs(strategy).await;
+        assert_eq!(vec![1, 2, 3], *inputs.lock().unwrap());
+        assert_eq!(3, attempts);
+
+        assert_eq!(3, check_attempt_inputs(LinearRetryStrategy::new().max_attempts(2)).await);
+        assert_eq!(
+            2,
+            check_attempt_inputs(LinearRetryStrategy::new().max_attempts(2).one_based()).await
+        );
+    }
+
+    #[tokio::test]
+    async fn check_starting_attempt_beyond_max() {
+        let strategies: Vec<Box<dyn RetryStrategy>> = vec![
+            Box::new(LinearRetryStrategy::new().max_attempts(2)),
+            Box::new(ExponentialRetryStrategy::new().max_attempts(2)),
+            Box::new(GrowingLinearStrategy::new().max_attempts(2)),
+            Box::new(SlidingWindowStrategy::new().max_attempts(2)),
+            Box::new(RateLimitedStrategy::new().max_attempts(2)),
+        ];
+        for strategy in strategies {
+            let mut attempts = 0;
+            let error = RetryFuture::new(
+                || {
+                    attempts += 1;
+                    async { Err::<(), _>(RetryPolicy::<()>::Retry(None)) }
+                },
+                strategy,
+            )
+            .starting_attempt(5)
+            .await
+            .unwrap_err();
+            assert_eq!(crate::StopReason::TooManyAttempts, error.reason());
+            assert_eq!(1, attempts);
+        }
+    }
+
+    #[test]
+    fn check_names() {
+        assert_eq!("linear", LinearRetryStrategy::new().name());
+        assert_eq!("exponential", ExponentialRetryStrategy::new().name());
+        assert_eq!("infinite", InfiniteRetryStrategy::new().name());
+        assert_eq!("sliding_window", SlidingWindowStrategy::new().name());
+        assert_eq!("rate_limited", RateLimitedStrategy::new().name());
+        assert_eq!("growing_linear", GrowingLinearStrategy::new().name());
+        assert_eq!("poll", PollStrategy::new().name());
+        assert_eq!("no_retry", NoRetryStrategy.name());
+        assert_eq!("fn", FnStrategy::new(|_, _| None, 1).name());
+        assert_eq!("iterator", IteratorStrategy::new([Duration::ZERO]).name());
+        assert_eq!("round_robin", RoundRobinStrategy::new(vec![Duration::ZERO]).name());
+        assert_eq!("linear", RetryStrategy::name(&&mut LinearRetryStrategy::new()));
+    }
+
+    #[test]
+    fn check_validate() {
+        fn reason(strategy: impl RetryStrategy) -> String {
+            strategy.validate().unwrap_err().reason
+        }
+
+        assert_eq!(
+            "max attempts must be positive",
+            reason(LinearRetryStrategy::new().max_attempts(0))
+        );
+        assert_eq!(
+            "max attempts must be positive",
+            reason(GrowingLinearStrategy::new().max_attempts(0))
+        );
+        assert_eq!("max attempts must be positive", reason(FnStrategy::new(|_, _| None, 0)));
+        assert_eq!(
+            "max attempts must be positive",
+            reason(IteratorStrategy::new([Duration::ZERO]).max_attempts(0))
+        );
+        assert_eq!("max polls must be positive", reason(PollStrategy::new().max_polls(0)));
+        assert_eq!(
+            "quantum must be positive",
+            reason(LinearRetryStrategy::new().quantize(Duration::ZERO))
+        );
+
+        let exponential = ExponentialRetryStrategy::new();
+        assert_eq!(
+            "base must be at least 1",
+            reason(ExponentialRetryStrategy { base: 0, ..exponential })
+        );
+        let multiplier_range = Some((2.0, 1.5));
+        assert!(reason(ExponentialRetryStrategy { multiplier_range, ..exponential })
+            .contains("1 <= min"));
+
+        assert_eq!("permits must be positive", reason(RateLimitedStrategy::new().permits(0)));
+        assert_eq!(
+            "window size must be positive",
+            reason(SlidingWindowStrategy::new().window_size(0))
+        );
+        assert!(reason(SlidingWindowStrategy::new().failure_rate_threshold(1.5))
+            .contains("from 0 to 1"));
+        assert!(reason(SlidingWindowStrategy::new().failure_rate_threshold(f64::NAN))
+            .contains("from 0 to 1"));
+
+        let mut round_robin = RoundRobinStrategy::new(vec![Duration::ZERO]);
+        assert_eq!("max attempts must be positive", reason(round_robin.clone().max_attempts(0)));
+        round_robin.delays.clear();
+        assert_eq!("delays must not be empty", reason(round_robin));
+
+        for strategy in [
+            Box::new(LinearRetryStrategy::new()) as Box<dyn RetryStrategy>,
+            Box::new(ExponentialRetryStrategy::new().multiplier_range(1.5, 2.0)),
+            Box::new(GrowingLinearStrategy::new()),
+            Box::new(InfiniteRetryStrategy::new()),
+            Box::new(PollStrategy::new()),
+            Box::new(RateLimitedStrategy::new()),
+            Box::new(RoundRobinStrategy::new(vec![Duration::ZERO])),
+            Box::new(SlidingWindowStrategy::new()),
+            Box::new(FnStrategy::new(|_, _| None, 1)),
+            Box::new(IteratorStrategy::new([Duration::ZERO])),
+        ] {
+            assert_eq!(Ok(()), strategy.validate(), "{}", strategy.name());
+        }
+    }
+
+    #[test]
+    fn check_try_new() {
+        let error = RetryFuture::<_, _, _, ()>::try_new(
+            || async { Ok::<_, RetryPolicy<()>>(()) },
+            Arc::new(Mutex::new(LinearRetryStrategy::new().max_attempts(0))),
+        )
+        .map(|_| ())
+        .unwrap_err();
+        assert_eq!(
+            "invalid configuration of linear retry strategy: max attempts must be positive",
+            error.to_string()
+        );
+    }
+
+    async fn fail_once<RS: RetryStrategy>(strategy: RS) {
+        let mut attempt = 0;
+        RetryFuture::new(
+            || {
+                attempt += 1;
+                let result =
+                    if attempt == 1 { Err(RetryPolicy::<()>::Retry(None)) } else { Ok(()) };
+                async move { result }
+            },
+            strategy,
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn check_rc_ref_cell() {
+        let strategy = Rc::new(RefCell::new(SlidingWindowStrategy::new()));
+        let local = tokio::task::LocalSet::new();
+        local
+            .run_until(async {
+                tokio::task::spawn_local(fail_once(strategy.clone())).await.unwrap();
+                tokio::task::spawn_local(fail_once(strategy.clone())).await.unwrap();
+            })
+            .await;
+        assert_eq!(2, strategy.borrow().failures());
+        assert_eq!("sliding_window", strategy.name());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn check_arc_mutex() {
+        let strategy = Arc::new(Mutex::new(SlidingWindowStrategy::new()));
+        let (a, b) = tokio::join!(
+            tokio::spawn(fail_once(strategy.clone())),
+            tokio::spawn(fail_once(strategy.clone()))
+        );
+        a.unwrap();
+        b.unwrap();
+        assert_eq!(2, strategy.lock().unwrap().failures());
+    }
 }
@@ -0,0 +1,99 @@
+use crate::{RetryStop, RetryStrategy};
+use futures::future::BoxFuture;
+use std::time::Duration;
+
+/// Decision of a strategy which is awaited by [RetryFuture](crate::RetryFuture)
+/// before sleeping, see [RetryStrategy::check_attempt_async]
+pub type AsyncDecision = BoxFuture<'static, Result<Duration, RetryStop>>;
+
+/// Retry strategy which needs to wait for something before deciding,
+/// e.g. to ask a remote rate limiter or to read a circuit breaker from a database.
+///
+/// The returned future must not borrow the strategy, so clone what it needs into it.
+/// Use it with [RetryFuture](crate::RetryFuture) by wrapping into [AsyncStrategy].
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::{AsyncDecision, AsyncRetryStrategy, AsyncStrategy, RetryFuture, RetryStop};
+/// use retry_future::RetryPolicy;
+/// use std::time::Duration;
+///
+/// struct AskLimiter;
+///
+/// impl AsyncRetryStrategy for AskLimiter {
+///     fn check_attempt(&mut self, attempts_before: usize) -> AsyncDecision {
+///         Box::pin(async move {
+///             // e.g. a request to a rate limiter
+///             tokio::task::yield_now().await;
+///             match attempts_before {
+///                 0..=2 => Ok(Duration::from_millis(1)),
+///                 _ => Err(RetryStop::MaxAttempts),
+///             }
+///         })
+///     }
+///
+///     fn retry_early_returned_errors(&self) -> bool {
+///         true
+///     }
+/// }
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let error = RetryFuture::new(
+///     || async { Err::<(), _>(RetryPolicy::<()>::Retry(None)) },
+///     AsyncStrategy(AskLimiter),
+/// )
+/// .await
+/// .unwrap_err();
+/// assert_eq!(4, error.len());
+/// # }
+/// ```
+pub trait AsyncRetryStrategy {
+    /// Same as [RetryStrategy::check_attempt], but the decision is awaited
+    fn check_attempt(&mut self, attempts_before: usize) -> AsyncDecision;
+
+    /// See [RetryStrategy::retry_early_returned_errors]
+    fn retry_early_returned_errors(&self) -> bool;
+
+    /// See [RetryStrategy::record_outcome]
+    fn record_outcome(&mut self, _success: bool) {}
+
+    /// See [RetryStrategy::name]
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+}
+
+/// Adapter which allows to use [AsyncRetryStrategy] wherever [RetryStrategy] is expected.
+///
+/// The decisions are awaited by [RetryFuture](crate::RetryFuture), including
+/// [routes](crate::RetryFuture::with_strategy_for) and [hedging](crate::RetryFuture::hedged),
+/// and by [retry_items](crate::RetryStreamExt::retry_items). Synchronous
+/// [check_attempt](RetryStrategy::check_attempt) always stops retrying, and the delay
+/// cannot be peeked, so [attempt_timeout_from_strategy](crate::RetryFuture::attempt_timeout_from_strategy)
+/// has no effect with it.
+#[derive(Debug, Copy, Clone)]
+pub struct AsyncStrategy<S>(pub S);
+
+impl<S: AsyncRetryStrategy> RetryStrategy for AsyncStrategy<S> {
+    fn check_attempt(&mut self, _attempts_before: usize) -> Result<Duration, RetryStop> {
+        Err(RetryStop::MaxAttempts)
+    }
+
+    fn check_attempt_async(&mut self, attempts_before: usize) -> Option<AsyncDecision> {
+        Some(self.0.check_attempt(attempts_before))
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        self.0.retry_early_returned_errors()
+    }
+
+    fn record_outcome(&mut self, success: bool) {
+        self.0.record_outcome(success)
+    }
+
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+}
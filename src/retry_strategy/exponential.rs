@@ -1,4 +1,6 @@
-use crate::{RetryStrategy, TooManyAttempts};
+use crate::{saturating_mul, EnvError, RetryStop, RetryStrategy, StrategyConfigError};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::time::Duration;
 
 /// Retry futures exponentially.
@@ -10,12 +12,10 @@ use std::time::Duration;
 /// use retry_future::ExponentialRetryStrategy;
 /// use std::time::Duration;
 ///
-/// let mut strategy = ExponentialRetryStrategy {
-///     base: 3,
-///     max_attempts: 5,
-///     initial_delay: Duration::from_secs(1),
-///     ..Default::default()
-/// };
+/// let mut strategy = ExponentialRetryStrategy::new()
+///     .base(3)
+///     .max_attempts(5)
+///     .initial_delay(Duration::from_secs(1));
 ///
 /// assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_secs(1));
 /// assert_eq!(strategy.check_attempt(1).unwrap(), Duration::from_secs(3));
@@ -25,11 +25,31 @@ use std::time::Duration;
 ///
 /// assert!(strategy.check_attempt(5).is_err());
 /// ```
+///
+/// Fields may be added in minor versions, so the strategy is built with
+/// [new](ExponentialRetryStrategy::new) and builder methods rather than a struct literal.
 #[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
 pub struct ExponentialRetryStrategy {
     pub base: usize,
     pub max_attempts: usize,
+    /// Delay before the first retry. If it is zero, the first retry is immediate
+    /// and the following delays grow from 1 millisecond, i.e. `0, 1ms, 2ms, 4ms...` with base `2`
     pub initial_delay: Duration,
+    /// Give up as soon as the sum of all delays would exceed this value,
+    /// even if there are attempts left.
+    ///
+    /// Only backoff is counted, not the time spent in attempts, so unlike a wall-clock
+    /// deadline such as [until](crate::RetryFuture::until) the same attempts always give up
+    /// at the same point. Giving up is reported as [RetryStop::Budget].
+    pub max_total_delay: Option<Duration>,
+    /// Instead of `base`, every delay is the previous one multiplied by a random factor
+    /// from this inclusive range
+    pub multiplier_range: Option<(f64, f64)>,
+    /// Seed of random factors of [multiplier_range](ExponentialRetryStrategy::multiplier_range).
+    /// A random one is picked on the first [check_attempt](RetryStrategy::check_attempt)
+    /// if it is not set
+    pub seed: Option<u64>,
     /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
     pub retry_early_returned_errors: bool,
 }
@@ -40,6 +60,9 @@ impl Default for ExponentialRetryStrategy {
             base: 2,
             max_attempts: 3,
             initial_delay: Duration::from_millis(500),
+            max_total_delay: None,
+            multiplier_range: None,
+            seed: None,
             retry_early_returned_errors: true,
         }
     }
@@ -55,37 +78,286 @@ impl ExponentialRetryStrategy {
         self
     }
 
+    /// See [initial_delay](ExponentialRetryStrategy::initial_delay)
     pub fn initial_delay(mut self, initial_delay: Duration) -> Self {
         self.initial_delay = initial_delay;
         self
     }
 
+    /// [initial_delay](ExponentialRetryStrategy::initial_delay) in milliseconds
+    ///
+    /// ```rust
+    /// use retry_future::ExponentialRetryStrategy;
+    /// use std::time::Duration;
+    ///
+    /// let strategy = ExponentialRetryStrategy::new().initial_delay_ms(250);
+    /// assert_eq!(Duration::from_millis(250), strategy.initial_delay);
+    /// ```
+    pub fn initial_delay_ms(self, initial_delay: u64) -> Self {
+        self.initial_delay(Duration::from_millis(initial_delay))
+    }
+
+    /// [initial_delay](ExponentialRetryStrategy::initial_delay) parsed from a string like
+    /// `"500ms"` or `"2s"`, as do other `*_str` setters of the strategies
+    ///
+    /// ```rust
+    /// use retry_future::ExponentialRetryStrategy;
+    /// use std::time::Duration;
+    ///
+    /// let strategy = ExponentialRetryStrategy::new().initial_delay_str("1s 500ms").unwrap();
+    /// assert_eq!(Duration::from_millis(1500), strategy.initial_delay);
+    /// assert!(ExponentialRetryStrategy::new().initial_delay_str("soon").is_err());
+    /// ```
+    #[cfg(feature = "humantime")]
+    pub fn initial_delay_str(self, initial_delay: &str) -> Result<Self, humantime::DurationError> {
+        Ok(self.initial_delay(humantime::parse_duration(initial_delay)?))
+    }
+
+    /// [initial_delay](ExponentialRetryStrategy::initial_delay) in seconds
+    pub fn initial_delay_secs(self, initial_delay: u64) -> Self {
+        self.initial_delay(Duration::from_secs(initial_delay))
+    }
+
+    /// Delay is multiplied by `base` after every attempt. Base `1` gives constant delays.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `base` is `0`, as all delays after the first one would be zero.
+    pub fn base(mut self, base: usize) -> Self {
+        assert!(base >= 1, "base of ExponentialRetryStrategy must be at least 1");
+        self.base = base;
+        self
+    }
+
+    /// Reads configuration from `{prefix}_MAX_ATTEMPTS`, `{prefix}_INITIAL_DELAY_MS`
+    /// and `{prefix}_BASE` environment variables, using [defaults](Default) for missing ones.
+    ///
+    /// Returns an error if a variable cannot be parsed or the base is `0`.
+    ///
+    /// ```rust
+    /// use retry_future::ExponentialRetryStrategy;
+    /// use std::time::Duration;
+    ///
+    /// std::env::set_var("MY_APP_RETRY_INITIAL_DELAY_MS", "250");
+    /// let strategy = ExponentialRetryStrategy::from_env("MY_APP_RETRY").unwrap();
+    /// assert_eq!(Duration::from_millis(250), strategy.initial_delay);
+    /// ```
+    pub fn from_env(prefix: &str) -> Result<Self, EnvError> {
+        fn var<T: std::str::FromStr>(prefix: &str, name: &str) -> Result<Option<T>, EnvError> {
+            let var = format!("{prefix}_{name}");
+            let Some(value) = std::env::var_os(&var) else {
+                return Ok(None);
+            };
+            match value.to_str().map(str::parse) {
+                Some(Ok(value)) => Ok(Some(value)),
+                _ => Err(EnvError { var, value: value.to_string_lossy().into_owned() }),
+            }
+        }
+
+        let mut strategy = Self::default();
+        if let Some(max_attempts) = var(prefix, "MAX_ATTEMPTS")? {
+            strategy.max_attempts = max_attempts;
+        }
+        if let Some(initial_delay) = var(prefix, "INITIAL_DELAY_MS")? {
+            strategy.initial_delay = Duration::from_millis(initial_delay);
+        }
+        match var(prefix, "BASE")? {
+            Some(0) => {
+                return Err(EnvError { var: format!("{prefix}_BASE"), value: String::from("0") })
+            }
+            Some(base) => strategy.base = base,
+            None => {}
+        }
+        Ok(strategy)
+    }
+
+    /// See [max_total_delay](ExponentialRetryStrategy::max_total_delay)
+    pub fn max_total_delay(mut self, max_total_delay: Duration) -> Self {
+        self.max_total_delay = Some(max_total_delay);
+        self
+    }
+
+    /// Alias of [max_total_delay](ExponentialRetryStrategy::max_total_delay), stressing that
+    /// only the backoff is counted, not the time spent in attempts
+    pub fn max_total_backoff(self, max_total_backoff: Duration) -> Self {
+        self.max_total_delay(max_total_backoff)
+    }
+
+    /// [max_total_delay](ExponentialRetryStrategy::max_total_delay) in milliseconds
+    pub fn max_total_delay_ms(self, max_total_delay: u64) -> Self {
+        self.max_total_delay(Duration::from_millis(max_total_delay))
+    }
+
+    /// [max_total_delay](ExponentialRetryStrategy::max_total_delay)
+    /// parsed from a string like `"2s"`
+    #[cfg(feature = "humantime")]
+    pub fn max_total_delay_str(
+        self,
+        max_total_delay: &str,
+    ) -> Result<Self, humantime::DurationError> {
+        Ok(self.max_total_delay(humantime::parse_duration(max_total_delay)?))
+    }
+
+    /// [max_total_delay](ExponentialRetryStrategy::max_total_delay) in seconds
+    pub fn max_total_delay_secs(self, max_total_delay: u64) -> Self {
+        self.max_total_delay(Duration::from_secs(max_total_delay))
+    }
+
+    /// See [multiplier_range](ExponentialRetryStrategy::multiplier_range)
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `1 <= min <= max`, so that delays never shrink.
+    pub fn multiplier_range(mut self, min: f64, max: f64) -> Self {
+        assert!(
+            1.0 <= min && min <= max,
+            "multiplier range of ExponentialRetryStrategy must satisfy 1 <= min <= max"
+        );
+        self.multiplier_range = Some((min, max));
+        self
+    }
+
+    /// See [seed](ExponentialRetryStrategy::seed)
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
     /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
     pub fn retry_early_returned_errors(mut self, retry_early_returned_errors: bool) -> Self {
         self.retry_early_returned_errors = retry_early_returned_errors;
         self
     }
+
+    /// Delays before every retry in order, each with `true` if the following ones are the same,
+    /// so that callers can stop iterating instead of walking through every attempt
+    fn delays(&self) -> impl Iterator<Item = (Duration, bool)> {
+        let (first, initial_delay) = match self.initial_delay.is_zero() {
+            true => (Some((Duration::ZERO, false)), Duration::from_millis(1)),
+            false => (None, self.initial_delay),
+        };
+        let base = self.base as u64;
+        // factors are drawn in the same order on every call, so that
+        // every delay is the previous one multiplied by the next factor
+        let mut random = self.multiplier_range.zip(self.seed.map(StdRng::seed_from_u64));
+        let mut power = 1_u64;
+        let mut multiplier = 1.0_f64;
+        let growing = std::iter::from_fn(move || {
+            Some(match &mut random {
+                Some(((min, max), rng)) => {
+                    let delay =
+                        Duration::try_from_secs_f64(initial_delay.as_secs_f64() * multiplier)
+                            .unwrap_or(Duration::MAX);
+                    let stable = delay == Duration::MAX || *max == 1.0;
+                    if !stable {
+                        multiplier *= rng.gen_range(*min..=*max);
+                    }
+                    (delay, stable)
+                }
+                None => {
+                    let delay = saturating_mul(initial_delay, power);
+                    let stable = base == 1 || power == u64::MAX || delay == Duration::MAX;
+                    power = power.saturating_mul(base);
+                    (delay, stable)
+                }
+            })
+        });
+        first.into_iter().chain(growing)
+    }
+
+    fn delay(&self, attempts_before: usize) -> Duration {
+        let mut delays = self.delays().enumerate();
+        let found = delays.find(|(n, (_, stable))| *n == attempts_before || *stable);
+        found.map(|(_, (delay, _))| delay).expect("delays never end")
+    }
+
+    /// Whether delays before `attempts_before + 1` retries add up to more than `max_total_delay`
+    fn exceeds_total_delay(&self, attempts_before: usize, max_total_delay: Duration) -> bool {
+        let mut total = Duration::ZERO;
+        for (n, (delay, stable)) in self.delays().enumerate() {
+            if stable {
+                let remaining = (attempts_before - n) as u64 + 1;
+                return total.saturating_add(saturating_mul(delay, remaining)) > max_total_delay;
+            }
+            total = total.saturating_add(delay);
+            if total > max_total_delay {
+                return true;
+            }
+            if n == attempts_before {
+                return false;
+            }
+        }
+        unreachable!("delays never end")
+    }
 }
 
 impl RetryStrategy for ExponentialRetryStrategy {
-    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, TooManyAttempts> {
-        let exponent = self.base.pow(attempts_before as u32);
-        if self.max_attempts == attempts_before {
-            Err(TooManyAttempts)
-        } else {
-            Ok(self.initial_delay * exponent as u32)
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        if attempts_before >= self.max_attempts {
+            return Err(RetryStop::MaxAttempts);
         }
+        if self.multiplier_range.is_some() && self.seed.is_none() {
+            self.seed = Some(rand::random());
+        }
+        if let Some(max_total_delay) = self.max_total_delay {
+            if self.exceeds_total_delay(attempts_before, max_total_delay) {
+                return Err(RetryStop::Budget);
+            }
+        }
+        Ok(self.delay(attempts_before))
+    }
+
+    fn peek_next_delay(&self, attempts_before: usize) -> Option<Duration> {
+        if self.multiplier_range.is_some() && self.seed.is_none() {
+            return None;
+        }
+        { *self }.check_attempt(attempts_before).ok()
     }
 
     fn retry_early_returned_errors(&self) -> bool {
         self.retry_early_returned_errors
     }
+
+    fn name(&self) -> &'static str {
+        "exponential"
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        super::validate_max_attempts(self.name(), self.max_attempts)?;
+        if self.base == 0 {
+            return Err(StrategyConfigError::new(self.name(), "base must be at least 1"));
+        }
+        match self.multiplier_range {
+            Some((min, max)) if !(1.0 <= min && min <= max) => Err(StrategyConfigError::new(
+                self.name(),
+                format!("multiplier range {min}..={max} must satisfy 1 <= min <= max"),
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "humantime")]
+    #[test]
+    fn check_duration_strings() {
+        let strategy = ExponentialRetryStrategy::new()
+            .initial_delay_str("500ms")
+            .unwrap()
+            .max_total_delay_str("2m 30s")
+            .unwrap();
+        assert_eq!(Duration::from_millis(500), strategy.initial_delay);
+        assert_eq!(Some(Duration::from_secs(150)), strategy.max_total_delay);
+
+        assert!(ExponentialRetryStrategy::new().initial_delay_str("").is_err());
+        assert!(ExponentialRetryStrategy::new().initial_delay_str("500").is_err());
+        assert!(ExponentialRetryStrategy::new().initial_delay_str("5 parsecs").is_err());
+        assert!(ExponentialRetryStrategy::new().max_total_delay_str("-1s").is_err());
+    }
+
     #[test]
     fn check_exponent() {
         let mut strategy = ExponentialRetryStrategy {
@@ -100,6 +372,201 @@ mod tests {
         assert_eq!(strategy.check_attempt(3).unwrap(), Duration::from_secs(8));
         assert_eq!(strategy.check_attempt(4).unwrap(), Duration::from_secs(16));
 
-        assert!(strategy.check_attempt(5).is_err());
+        assert_eq!(Err(RetryStop::MaxAttempts), strategy.check_attempt(5));
+    }
+
+    #[test]
+    fn check_base() {
+        let mut strategy =
+            ExponentialRetryStrategy::new().base(1).initial_delay(Duration::from_secs(1));
+        assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_secs(1));
+        assert_eq!(strategy.check_attempt(2).unwrap(), Duration::from_secs(1));
+
+        let mut strategy = strategy.base(10);
+        assert_eq!(strategy.check_attempt(2).unwrap(), Duration::from_secs(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "must be at least 1")]
+    fn check_zero_base() {
+        ExponentialRetryStrategy::new().base(0);
+    }
+
+    #[test]
+    fn check_max_total_delay() {
+        let mut strategy = ExponentialRetryStrategy::new()
+            .max_attempts(10)
+            .initial_delay(Duration::from_secs(1))
+            .max_total_delay(Duration::from_secs(10));
+        assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_secs(1));
+        assert_eq!(strategy.check_attempt(1).unwrap(), Duration::from_secs(2));
+        assert_eq!(strategy.check_attempt(2).unwrap(), Duration::from_secs(4));
+
+        // 1 + 2 + 4 + 8 = 15 seconds in total
+        assert_eq!(Err(RetryStop::Budget), strategy.check_attempt(3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn check_max_total_delay_ignores_attempt_duration() {
+        let started = tokio::time::Instant::now();
+        let mut attempts = 0;
+        let error = crate::RetryFuture::new(
+            || {
+                attempts += 1;
+                async {
+                    tokio::time::sleep(Duration::from_secs(100)).await;
+                    Err::<(), _>(crate::RetryPolicy::<()>::Retry(None))
+                }
+            },
+            ExponentialRetryStrategy::new()
+                .max_attempts(10)
+                .initial_delay(Duration::from_secs(1))
+                .max_total_delay(Duration::from_secs(10)),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(crate::StopReason::Budget, error.reason());
+        // backoff of 1 + 2 + 4 seconds fits, the next 8 seconds would cross the cap
+        assert_eq!(4, attempts);
+        assert_eq!(Duration::from_secs(4 * 100 + 7), started.elapsed());
+    }
+
+    #[test]
+    fn check_huge_attempts_before() {
+        let attempts_before = usize::MAX - 1;
+        let strategy = ExponentialRetryStrategy::new()
+            .max_attempts(usize::MAX)
+            .initial_delay(Duration::from_secs(1));
+        assert_eq!(Ok(Duration::from_secs(u64::MAX)), { strategy }.check_attempt(attempts_before));
+        let mut capped = strategy.max_total_delay(Duration::from_secs(3600));
+        assert_eq!(Err(RetryStop::Budget), capped.check_attempt(attempts_before));
+
+        // constant delays are multiplied instead of added up one by one
+        let mut constant = strategy.base(1).max_total_delay(Duration::MAX);
+        assert_eq!(Ok(Duration::from_secs(1)), constant.check_attempt(attempts_before));
+        let mut constant = strategy.base(1).max_total_delay(Duration::from_secs(1 << 40));
+        assert_eq!(Ok(Duration::from_secs(1)), constant.check_attempt((1 << 40) - 1));
+        assert_eq!(Err(RetryStop::Budget), constant.check_attempt(1 << 40));
+
+        let mut random = strategy.multiplier_range(1.0, 1.01).seed(42);
+        assert_eq!(Ok(Duration::MAX), random.check_attempt(attempts_before));
+        let mut random = random.max_total_delay(Duration::from_secs(3600));
+        assert_eq!(Err(RetryStop::Budget), random.check_attempt(attempts_before));
+    }
+
+    #[test]
+    fn check_max_total_backoff() {
+        let mut strategy = ExponentialRetryStrategy::new()
+            .max_attempts(usize::MAX)
+            .initial_delay(Duration::from_secs(1))
+            .max_total_backoff(Duration::from_secs(10));
+        assert_eq!(Some(Duration::from_secs(10)), strategy.max_total_delay);
+        assert_eq!(Ok(Duration::from_secs(4)), strategy.check_attempt(2));
+        // 1 + 2 + 4 + 8 seconds cross the cap long before attempts run out
+        assert_eq!(Err(RetryStop::Budget), strategy.check_attempt(3));
+        assert_eq!(Err(RetryStop::Budget), strategy.check_attempt(usize::MAX - 1));
+    }
+
+    #[test]
+    fn check_zero_initial_delay() {
+        let mut strategy =
+            ExponentialRetryStrategy::new().max_attempts(5).initial_delay(Duration::ZERO).base(3);
+        let delays: Vec<_> = (0..5).map(|n| strategy.check_attempt(n).unwrap()).collect();
+        assert_eq!(delays, [0, 1, 3, 9, 27].map(Duration::from_millis));
+    }
+
+    #[test]
+    fn check_from_env() {
+        std::env::set_var("FROM_ENV_TEST_MAX_ATTEMPTS", "7");
+        std::env::set_var("FROM_ENV_TEST_INITIAL_DELAY_MS", "1500");
+        std::env::set_var("FROM_ENV_TEST_BASE", "3");
+        let strategy = ExponentialRetryStrategy::from_env("FROM_ENV_TEST").unwrap();
+        assert_eq!(7, strategy.max_attempts);
+        assert_eq!(Duration::from_millis(1500), strategy.initial_delay);
+        assert_eq!(3, strategy.base);
+
+        let strategy = ExponentialRetryStrategy::from_env("FROM_ENV_TEST_MISSING").unwrap();
+        let default = ExponentialRetryStrategy::default();
+        assert_eq!(default.max_attempts, strategy.max_attempts);
+        assert_eq!(default.initial_delay, strategy.initial_delay);
+        assert_eq!(default.base, strategy.base);
+    }
+
+    #[test]
+    fn check_from_env_invalid() {
+        std::env::set_var("FROM_ENV_INVALID_TEST_MAX_ATTEMPTS", "five");
+        assert_eq!(
+            Err(EnvError {
+                var: String::from("FROM_ENV_INVALID_TEST_MAX_ATTEMPTS"),
+                value: String::from("five")
+            }),
+            ExponentialRetryStrategy::from_env("FROM_ENV_INVALID_TEST").map(|_| ())
+        );
+
+        std::env::set_var("FROM_ENV_ZERO_BASE_TEST_BASE", "0");
+        let error = ExponentialRetryStrategy::from_env("FROM_ENV_ZERO_BASE_TEST").unwrap_err();
+        assert_eq!("FROM_ENV_ZERO_BASE_TEST_BASE", error.var);
+    }
+
+    #[test]
+    fn check_multiplier_range() {
+        let strategy = ExponentialRetryStrategy::new()
+            .max_attempts(10)
+            .initial_delay(Duration::from_secs(1))
+            .multiplier_range(1.5, 2.5);
+        let delays = |seed| {
+            let mut strategy = strategy.seed(seed);
+            (0..5).map(|n| strategy.check_attempt(n).unwrap()).collect::<Vec<_>>()
+        };
+        let seeded = delays(42);
+        let millis: Vec<_> = seeded.iter().map(Duration::as_millis).collect();
+        assert_eq!(vec![1000, 2026, 4139, 8844, 16856], millis);
+        assert_eq!(seeded, delays(42));
+        assert_ne!(seeded, delays(43));
+        assert_eq!(Duration::from_secs(1), seeded[0]);
+        for pair in seeded.windows(2) {
+            let multiplier = pair[1].as_secs_f64() / pair[0].as_secs_f64();
+            assert!((1.5..=2.5).contains(&multiplier), "{multiplier}");
+        }
+
+        let mut strategy = strategy.max_attempts(100);
+        assert_eq!(Duration::MAX, strategy.check_attempt(99).unwrap());
+        assert!(strategy.seed.is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "1 <= min <= max")]
+    fn check_shrinking_multiplier_range() {
+        ExponentialRetryStrategy::new().multiplier_range(0.5, 2.0);
+    }
+
+    #[test]
+    fn check_peek_next_delay() {
+        let mut strategy = ExponentialRetryStrategy::new()
+            .max_attempts(3)
+            .initial_delay(Duration::from_secs(1))
+            .max_total_delay(Duration::from_secs(5));
+        assert_eq!(Some(Duration::from_secs(2)), strategy.peek_next_delay(1));
+        assert_eq!(Ok(Duration::from_secs(2)), strategy.check_attempt(1));
+        // 1 + 2 + 4 seconds exceed the budget
+        assert_eq!(None, strategy.peek_next_delay(2));
+        assert_eq!(None, strategy.peek_next_delay(3));
+
+        let strategy = strategy.multiplier_range(1.5, 2.5);
+        assert_eq!(None, strategy.peek_next_delay(1));
+        let mut strategy = strategy.seed(7);
+        assert_eq!(strategy.peek_next_delay(1), strategy.check_attempt(1).ok());
+    }
+
+    #[test]
+    fn check_overflow() {
+        let mut strategy = ExponentialRetryStrategy::new()
+            .max_attempts(usize::MAX)
+            .initial_delay(Duration::from_secs(2));
+        assert_eq!(strategy.check_attempt(64).unwrap(), Duration::MAX);
+        assert_eq!(strategy.check_attempt(usize::MAX - 1).unwrap(), Duration::MAX);
+
+        let mut strategy = strategy.max_total_delay(Duration::from_secs(60));
+        assert!(strategy.check_attempt(64).is_err());
     }
 }
@@ -0,0 +1,127 @@
+use crate::{RetryStop, RetryStrategy, StrategyConfigError};
+use std::time::Duration;
+
+/// Retry strategy computing every delay with a closure from `attempts_before`
+/// and the previous delay, which is `None` before the first retry.
+/// The closure returns `None` to give up.
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::{FnStrategy, RetryStrategy};
+/// use std::time::Duration;
+///
+/// // doubles the previous delay
+/// let mut strategy = FnStrategy::new(
+///     |_, last: Option<Duration>| Some(last.map_or(Duration::from_secs(1), |last| last * 2)),
+///     3,
+/// );
+///
+/// assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_secs(1));
+/// assert_eq!(strategy.check_attempt(1).unwrap(), Duration::from_secs(2));
+/// assert_eq!(strategy.check_attempt(2).unwrap(), Duration::from_secs(4));
+///
+/// assert!(strategy.check_attempt(3).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct FnStrategy<F> {
+    f: F,
+    max_attempts: usize,
+    last_delay: Option<Duration>,
+    retry_early_returned_errors: bool,
+}
+
+impl<F> FnStrategy<F>
+where
+    F: FnMut(usize, Option<Duration>) -> Option<Duration>,
+{
+    pub fn new(f: F, max_attempts: usize) -> Self {
+        Self { f, max_attempts, last_delay: None, retry_early_returned_errors: true }
+    }
+
+    /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    pub fn retry_early_returned_errors(mut self, retry_early_returned_errors: bool) -> Self {
+        self.retry_early_returned_errors = retry_early_returned_errors;
+        self
+    }
+}
+
+impl<F> RetryStrategy for FnStrategy<F>
+where
+    F: FnMut(usize, Option<Duration>) -> Option<Duration>,
+{
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        if attempts_before >= self.max_attempts {
+            return Err(RetryStop::MaxAttempts);
+        }
+        // a new sequence of attempts, e.g. after a success
+        if attempts_before == 0 {
+            self.last_delay = None;
+        }
+        let delay = (self.f)(attempts_before, self.last_delay).ok_or(RetryStop::MaxAttempts)?;
+        self.last_delay = Some(delay);
+        Ok(delay)
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        self.retry_early_returned_errors
+    }
+
+    fn name(&self) -> &'static str {
+        "fn"
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        super::validate_max_attempts(self.name(), self.max_attempts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExponentialRetryStrategy;
+
+    #[test]
+    fn check_exponential() {
+        let mut exponential = ExponentialRetryStrategy::new()
+            .max_attempts(6)
+            .initial_delay(Duration::from_millis(100));
+        let mut strategy = FnStrategy::new(
+            |_, last: Option<Duration>| {
+                Some(last.map_or(Duration::from_millis(100), |last| last * 2))
+            },
+            6,
+        );
+        for n in 0..=6 {
+            assert_eq!(exponential.check_attempt(n), strategy.check_attempt(n));
+        }
+    }
+
+    #[test]
+    fn check_fibonacci() {
+        let mut previous = Duration::ZERO;
+        let mut strategy = FnStrategy::new(
+            move |_, last: Option<Duration>| {
+                let last = last.unwrap_or(Duration::from_secs(1));
+                let next = previous + last;
+                previous = last;
+                // give up once delays exceed a minute
+                (next <= Duration::from_secs(60)).then_some(next)
+            },
+            usize::MAX,
+        );
+        let delays: Vec<_> = (0..).map_while(|n| strategy.check_attempt(n).ok()).collect();
+        assert_eq!(delays, [1, 2, 3, 5, 8, 13, 21, 34, 55].map(Duration::from_secs));
+    }
+
+    #[test]
+    fn check_reset() {
+        let mut strategy = FnStrategy::new(
+            |n, last| Some(last.unwrap_or_default() + Duration::from_secs(n as u64 + 1)),
+            5,
+        );
+        assert_eq!(Ok(Duration::from_secs(1)), strategy.check_attempt(0));
+        assert_eq!(Ok(Duration::from_secs(3)), strategy.check_attempt(1));
+        assert_eq!(Ok(Duration::from_secs(1)), strategy.check_attempt(0));
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{LinearRetryStrategy, RetryStrategy};
+use std::sync::{Arc, PoisonError, RwLock};
+
+type MakeStrategy = Arc<dyn Fn() -> Box<dyn RetryStrategy + Send> + Send + Sync>;
+
+static DEFAULT_STRATEGY: RwLock<Option<MakeStrategy>> = RwLock::new(None);
+
+/// Replaces the strategy returned by [default_strategy], e.g. once at startup
+/// with a policy tuned for the whole application.
+///
+/// It can be called again at any time. [RetryFutures](crate::RetryFuture) created before
+/// keep their copy of the previous strategy.
+///
+/// ```rust
+/// use retry_future::{set_default_strategy, LinearRetryStrategy, RetryFuture, RetryPolicy};
+/// use std::time::Duration;
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// set_default_strategy(LinearRetryStrategy::new().delay_between_retries(Duration::from_millis(1)));
+///
+/// let x = RetryFuture::with_default(|| async { Ok::<_, RetryPolicy>(1) }).await.unwrap();
+/// assert_eq!(1, x);
+/// # }
+/// ```
+pub fn set_default_strategy<RS>(retry_strategy: RS)
+where
+    RS: RetryStrategy + Clone + Send + Sync + 'static,
+{
+    let make: MakeStrategy = Arc::new(move || Box::new(retry_strategy.clone()));
+    *DEFAULT_STRATEGY.write().unwrap_or_else(PoisonError::into_inner) = Some(make);
+}
+
+/// Copy of the strategy configured with [set_default_strategy]
+/// or [LinearRetryStrategy] with default settings if there is none
+pub fn default_strategy() -> Box<dyn RetryStrategy + Send> {
+    let make = DEFAULT_STRATEGY.read().unwrap_or_else(PoisonError::into_inner).clone();
+    match make {
+        Some(make) => make(),
+        None => Box::new(LinearRetryStrategy::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RetryFuture, RetryPolicy, StopReason};
+    use std::time::Duration;
+
+    async fn attempts_with_default() -> usize {
+        let error =
+            RetryFuture::with_default(|| async { Err::<(), _>(RetryPolicy::<()>::Retry(None)) })
+                .await
+                .unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        error.len()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reconfigure() {
+        set_default_strategy(LinearRetryStrategy::new().max_attempts(1));
+        assert_eq!(2, attempts_with_default().await);
+
+        let strategy = Arc::new(std::sync::Mutex::new(
+            LinearRetryStrategy::new().max_attempts(3).delay_between_retries(Duration::ZERO),
+        ));
+        set_default_strategy(strategy);
+        assert_eq!(4, attempts_with_default().await);
+        assert_eq!("linear", default_strategy().name());
+    }
+}
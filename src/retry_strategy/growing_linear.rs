@@ -0,0 +1,148 @@
+use crate::{saturating_mul, RetryStop, RetryStrategy, StrategyConfigError};
+use std::time::Duration;
+
+/// Retry strategy which grows delay linearly, i.e. `base_delay + increment * attempts_before`.
+///
+/// Unlike [LinearRetryStrategy](crate::LinearRetryStrategy), which keeps delay constant.
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::RetryStrategy;
+/// use retry_future::GrowingLinearStrategy;
+/// use std::time::Duration;
+///
+/// let mut strategy = GrowingLinearStrategy::new()
+///     .base_delay(Duration::from_secs(1))
+///     .increment(Duration::from_secs(2))
+///     .max_attempts(4);
+///
+/// assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_secs(1));
+/// assert_eq!(strategy.check_attempt(1).unwrap(), Duration::from_secs(3));
+/// assert_eq!(strategy.check_attempt(2).unwrap(), Duration::from_secs(5));
+/// assert_eq!(strategy.check_attempt(3).unwrap(), Duration::from_secs(7));
+///
+/// assert!(strategy.check_attempt(4).is_err());
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct GrowingLinearStrategy {
+    pub base_delay: Duration,
+    pub increment: Duration,
+    pub max_attempts: usize,
+    /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    pub retry_early_returned_errors: bool,
+}
+
+impl Default for GrowingLinearStrategy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_millis(500),
+            increment: Duration::from_millis(500),
+            max_attempts: 5,
+            retry_early_returned_errors: true,
+        }
+    }
+}
+
+impl GrowingLinearStrategy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// [base_delay](GrowingLinearStrategy::base_delay) in milliseconds
+    pub fn base_delay_ms(self, base_delay: u64) -> Self {
+        self.base_delay(Duration::from_millis(base_delay))
+    }
+
+    /// [base_delay](GrowingLinearStrategy::base_delay) parsed from a string like `"2s"`
+    #[cfg(feature = "humantime")]
+    pub fn base_delay_str(self, base_delay: &str) -> Result<Self, humantime::DurationError> {
+        Ok(self.base_delay(humantime::parse_duration(base_delay)?))
+    }
+
+    /// [base_delay](GrowingLinearStrategy::base_delay) in seconds
+    pub fn base_delay_secs(self, base_delay: u64) -> Self {
+        self.base_delay(Duration::from_secs(base_delay))
+    }
+
+    pub fn increment(mut self, increment: Duration) -> Self {
+        self.increment = increment;
+        self
+    }
+
+    /// [increment](GrowingLinearStrategy::increment) in milliseconds
+    pub fn increment_ms(self, increment: u64) -> Self {
+        self.increment(Duration::from_millis(increment))
+    }
+
+    /// [increment](GrowingLinearStrategy::increment) parsed from a string like `"2s"`
+    #[cfg(feature = "humantime")]
+    pub fn increment_str(self, increment: &str) -> Result<Self, humantime::DurationError> {
+        Ok(self.increment(humantime::parse_duration(increment)?))
+    }
+
+    /// [increment](GrowingLinearStrategy::increment) in seconds
+    pub fn increment_secs(self, increment: u64) -> Self {
+        self.increment(Duration::from_secs(increment))
+    }
+
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    pub fn retry_early_returned_errors(mut self, retry_early_returned_errors: bool) -> Self {
+        self.retry_early_returned_errors = retry_early_returned_errors;
+        self
+    }
+}
+
+impl RetryStrategy for GrowingLinearStrategy {
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        if attempts_before >= self.max_attempts {
+            return Err(RetryStop::MaxAttempts);
+        }
+        let increment = saturating_mul(self.increment, attempts_before as u64);
+        Ok(self.base_delay.saturating_add(increment))
+    }
+
+    fn peek_next_delay(&self, attempts_before: usize) -> Option<Duration> {
+        { *self }.check_attempt(attempts_before).ok()
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        self.retry_early_returned_errors
+    }
+
+    fn name(&self) -> &'static str {
+        "growing_linear"
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        super::validate_max_attempts(self.name(), self.max_attempts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_overflow() {
+        let mut strategy = GrowingLinearStrategy::new()
+            .base_delay(Duration::from_secs(1))
+            .increment(Duration::MAX / 2)
+            .max_attempts(usize::MAX);
+        assert_eq!(strategy.check_attempt(2).unwrap(), Duration::MAX);
+        assert_eq!(strategy.check_attempt(usize::MAX - 1).unwrap(), Duration::MAX);
+
+        let mut strategy = strategy.base_delay(Duration::MAX).increment(Duration::ZERO);
+        assert_eq!(strategy.check_attempt(usize::MAX - 1).unwrap(), Duration::MAX);
+    }
+}
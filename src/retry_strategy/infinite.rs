@@ -1,17 +1,159 @@
-use crate::{RetryStrategy, TooManyAttempts};
+use crate::{Jitter, RetryStop, RetryStrategy};
 use std::time::Duration;
 
 /// Infinite retry
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::RetryStrategy;
+/// use retry_future::InfiniteRetryStrategy;
+/// use std::time::Duration;
+///
+/// let mut strategy = InfiniteRetryStrategy::new()
+///     .duration_between_retries(Duration::from_millis(100));
+///
+/// assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_millis(100));
+/// assert_eq!(strategy.check_attempt(1_000_000).unwrap(), Duration::from_millis(100));
+/// ```
+///
+/// Fields may be added in minor versions, so the strategy is built with
+/// [new](InfiniteRetryStrategy::new) and builder methods rather than a struct literal.
+#[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
 pub struct InfiniteRetryStrategy {
     pub duration_between_retries: Duration,
+    pub jitter: Option<Jitter>,
+    /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    pub retry_early_returned_errors: bool,
+}
+
+impl Default for InfiniteRetryStrategy {
+    fn default() -> Self {
+        Self {
+            duration_between_retries: Duration::from_secs(1),
+            jitter: None,
+            retry_early_returned_errors: true,
+        }
+    }
+}
+
+impl InfiniteRetryStrategy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn duration_between_retries(mut self, duration_between_retries: Duration) -> Self {
+        self.duration_between_retries = duration_between_retries;
+        self
+    }
+
+    /// [duration_between_retries](InfiniteRetryStrategy::duration_between_retries) in milliseconds
+    pub fn duration_between_retries_ms(self, duration_between_retries: u64) -> Self {
+        self.duration_between_retries(Duration::from_millis(duration_between_retries))
+    }
+
+    /// [duration_between_retries](InfiniteRetryStrategy::duration_between_retries)
+    /// parsed from a string like `"2s"`
+    #[cfg(feature = "humantime")]
+    pub fn duration_between_retries_str(
+        self,
+        duration_between_retries: &str,
+    ) -> Result<Self, humantime::DurationError> {
+        Ok(self.duration_between_retries(humantime::parse_duration(duration_between_retries)?))
+    }
+
+    /// [duration_between_retries](InfiniteRetryStrategy::duration_between_retries) in seconds
+    pub fn duration_between_retries_secs(self, duration_between_retries: u64) -> Self {
+        self.duration_between_retries(Duration::from_secs(duration_between_retries))
+    }
+
+    pub fn jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = Some(jitter);
+        self
+    }
+
+    /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    pub fn retry_early_returned_errors(mut self, retry_early_returned_errors: bool) -> Self {
+        self.retry_early_returned_errors = retry_early_returned_errors;
+        self
+    }
 }
 
 impl RetryStrategy for InfiniteRetryStrategy {
-    fn check_attempt(&mut self, _attempts_before: usize) -> Result<Duration, TooManyAttempts> {
-        Ok(self.duration_between_retries)
+    fn check_attempt(&mut self, _attempts_before: usize) -> Result<Duration, RetryStop> {
+        match self.jitter {
+            Some(jitter) => {
+                Ok(jitter.apply(self.duration_between_retries, &mut rand::thread_rng()))
+            }
+            None => Ok(self.duration_between_retries),
+        }
+    }
+
+    fn peek_next_delay(&self, _attempts_before: usize) -> Option<Duration> {
+        match self.jitter {
+            Some(_) => None,
+            None => Some(self.duration_between_retries),
+        }
     }
 
     fn retry_early_returned_errors(&self) -> bool {
-        true
+        self.retry_early_returned_errors
+    }
+
+    fn name(&self) -> &'static str {
+        "infinite"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RetryFuture, RetryPolicy};
+
+    #[test]
+    fn check_builder() {
+        let mut strategy = InfiniteRetryStrategy::new()
+            .duration_between_retries(Duration::from_secs(2))
+            .jitter(Jitter::Additive(Duration::from_secs(1)))
+            .retry_early_returned_errors(false);
+        assert!(!RetryStrategy::retry_early_returned_errors(&strategy));
+        for attempts_before in 0..100 {
+            let delay = strategy.check_attempt(attempts_before).unwrap();
+            assert!(delay >= Duration::from_secs(2) && delay <= Duration::from_secs(3));
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn check_early_returned_errors() {
+        let mut attempts = 0;
+        let result = RetryFuture::new(
+            || {
+                attempts += 1;
+                async { Ok::<u32, RetryPolicy>("not a number".parse()?) }
+            },
+            InfiniteRetryStrategy::new().retry_early_returned_errors(false),
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(1, attempts);
+
+        let mut attempts = 0;
+        let result = RetryFuture::new(
+            || {
+                attempts += 1;
+                let attempts = attempts;
+                async move {
+                    if attempts < 3 {
+                        Ok::<u32, RetryPolicy>("not a number".parse()?)
+                    } else {
+                        Ok(attempts)
+                    }
+                }
+            },
+            InfiniteRetryStrategy::new(),
+        )
+        .await;
+        assert_eq!(3, result.unwrap());
     }
 }
@@ -0,0 +1,99 @@
+use crate::{RetryStop, RetryStrategy, StrategyConfigError};
+use std::time::Duration;
+
+/// Retry strategy taking delays lazily from an iterator, e.g. a generator
+/// built with [std::iter::successors]. Gives up once the iterator is exhausted
+/// or after [max_attempts](IteratorStrategy::max_attempts).
+///
+/// The iterator is shared by all sequences of attempts and is not restarted after
+/// a success, so an infinite iterator is usually capped by `max_attempts` instead.
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::{IteratorStrategy, RetryStrategy};
+/// use std::time::Duration;
+///
+/// let mut strategy = IteratorStrategy::new([1, 5].map(Duration::from_secs));
+///
+/// assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_secs(1));
+/// assert_eq!(strategy.check_attempt(1).unwrap(), Duration::from_secs(5));
+///
+/// assert!(strategy.check_attempt(2).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct IteratorStrategy<I> {
+    delays: I,
+    max_attempts: usize,
+    retry_early_returned_errors: bool,
+}
+
+impl<I: Iterator<Item = Duration>> IteratorStrategy<I> {
+    pub fn new(delays: impl IntoIterator<IntoIter = I>) -> Self {
+        Self {
+            delays: delays.into_iter(),
+            max_attempts: usize::MAX,
+            retry_early_returned_errors: true,
+        }
+    }
+
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    pub fn retry_early_returned_errors(mut self, retry_early_returned_errors: bool) -> Self {
+        self.retry_early_returned_errors = retry_early_returned_errors;
+        self
+    }
+}
+
+impl<I: Iterator<Item = Duration>> RetryStrategy for IteratorStrategy<I> {
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        if attempts_before >= self.max_attempts {
+            return Err(RetryStop::MaxAttempts);
+        }
+        self.delays.next().ok_or(RetryStop::MaxAttempts)
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        self.retry_early_returned_errors
+    }
+
+    fn name(&self) -> &'static str {
+        "iterator"
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        super::validate_max_attempts(self.name(), self.max_attempts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RetryFuture, RetryPolicy, StopReason};
+
+    #[test]
+    fn check_infinite_iterator() {
+        let doubling = std::iter::successors(Some(Duration::from_millis(100)), |d| Some(*d * 2));
+        let mut strategy = IteratorStrategy::new(doubling).max_attempts(4);
+        let delays: Vec<_> = (0..).map_while(|n| strategy.check_attempt(n).ok()).collect();
+        assert_eq!(delays, [100, 200, 400, 800].map(Duration::from_millis));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn check_exhausted() {
+        let start = tokio::time::Instant::now();
+        let error = RetryFuture::new(
+            || async { Err::<(), _>(RetryPolicy::<()>::Retry(None)) },
+            IteratorStrategy::new(vec![Duration::from_secs(1), Duration::from_secs(2)]),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        assert_eq!(3, error.len());
+        assert_eq!(Duration::from_secs(3), start.elapsed());
+    }
+}
@@ -1,7 +1,25 @@
-use crate::{RetryStrategy, TooManyAttempts};
+use crate::{RetryStop, RetryStrategy, StrategyConfigError};
 use std::time::Duration;
 
 /// Simple retry strategy that is retrying futures after [Duration](std::time::Duration)
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::RetryStrategy;
+/// use retry_future::LinearRetryStrategy;
+/// use std::time::Duration;
+///
+/// let mut strategy = LinearRetryStrategy::new()
+///     .max_attempts(2)
+///     .delay_between_repeats(Duration::from_millis(100));
+///
+/// assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_millis(100));
+/// assert_eq!(strategy.check_attempt(1).unwrap(), Duration::from_millis(100));
+///
+/// assert!(strategy.check_attempt(2).is_err());
+/// ```
+#[derive(Debug, Copy, Clone)]
 pub struct LinearRetryStrategy {
     pub max_attempts: usize,
     pub delay_between_retries: Duration,
@@ -20,17 +38,29 @@ impl Default for LinearRetryStrategy {
 }
 
 impl RetryStrategy for LinearRetryStrategy {
-    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, TooManyAttempts> {
-        if self.max_attempts == attempts_before {
-            Err(TooManyAttempts)
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        if attempts_before >= self.max_attempts {
+            Err(RetryStop::MaxAttempts)
         } else {
             Ok(self.delay_between_retries)
         }
     }
 
+    fn peek_next_delay(&self, attempts_before: usize) -> Option<Duration> {
+        { *self }.check_attempt(attempts_before).ok()
+    }
+
     fn retry_early_returned_errors(&self) -> bool {
         self.retry_early_returned_errors
     }
+
+    fn name(&self) -> &'static str {
+        "linear"
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        super::validate_max_attempts(self.name(), self.max_attempts)
+    }
 }
 
 impl LinearRetryStrategy {
@@ -48,9 +78,58 @@ impl LinearRetryStrategy {
         self
     }
 
+    /// [delay_between_retries](LinearRetryStrategy::delay_between_retries) in milliseconds
+    pub fn delay_between_retries_ms(self, delay_between_retries: u64) -> Self {
+        self.delay_between_retries(Duration::from_millis(delay_between_retries))
+    }
+
+    /// [delay_between_retries](LinearRetryStrategy::delay_between_retries)
+    /// parsed from a string like `"2s"`
+    #[cfg(feature = "humantime")]
+    pub fn delay_between_retries_str(
+        self,
+        delay_between_retries: &str,
+    ) -> Result<Self, humantime::DurationError> {
+        Ok(self.delay_between_retries(humantime::parse_duration(delay_between_retries)?))
+    }
+
+    /// [delay_between_retries](LinearRetryStrategy::delay_between_retries) in seconds
+    ///
+    /// ```rust
+    /// use retry_future::LinearRetryStrategy;
+    /// use std::time::Duration;
+    ///
+    /// let strategy = LinearRetryStrategy::new().delay_between_retries_secs(2);
+    /// assert_eq!(Duration::from_secs(2), strategy.delay_between_retries);
+    /// ```
+    pub fn delay_between_retries_secs(self, delay_between_retries: u64) -> Self {
+        self.delay_between_retries(Duration::from_secs(delay_between_retries))
+    }
+
+    /// Alias for [delay_between_retries](LinearRetryStrategy::delay_between_retries)
+    pub fn delay_between_repeats(self, delay_between_repeats: Duration) -> Self {
+        self.delay_between_retries(delay_between_repeats)
+    }
+
     /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
     pub fn retry_early_returned_errors(mut self, retry_early_returned_errors: bool) -> Self {
         self.retry_early_returned_errors = retry_early_returned_errors;
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_peek_next_delay() {
+        let mut strategy = LinearRetryStrategy::new()
+            .max_attempts(2)
+            .delay_between_retries(Duration::from_secs(1));
+        assert_eq!(Some(Duration::from_secs(1)), strategy.peek_next_delay(0));
+        assert_eq!(Some(Duration::from_secs(1)), strategy.peek_next_delay(1));
+        assert_eq!(None, strategy.peek_next_delay(2));
+        assert_eq!(Err(RetryStop::MaxAttempts), strategy.check_attempt(2));
+    }
+}
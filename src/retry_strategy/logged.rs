@@ -0,0 +1,82 @@
+use crate::{AsyncDecision, RetryStop, RetryStrategy, StrategyConfigError};
+use std::time::Duration;
+
+/// Decorator which logs every decision of the inner strategy with `debug` level,
+/// e.g. to verify the math of a custom strategy.
+///
+/// Created by [RetryStrategy::logged].
+#[derive(Debug, Copy, Clone)]
+pub struct Logged<S>(pub S);
+
+impl<S: RetryStrategy> RetryStrategy for Logged<S> {
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        let decision = self.0.check_attempt(attempts_before);
+        match decision {
+            Ok(delay) => log::debug!(
+                "{}: attempts_before = {attempts_before}, retry in {delay:?}",
+                self.0.name()
+            ),
+            Err(stop) => log::debug!(
+                "{}: attempts_before = {attempts_before}, give up - {stop}",
+                self.0.name()
+            ),
+        }
+        decision
+    }
+
+    fn check_attempt_async(&mut self, attempts_before: usize) -> Option<AsyncDecision> {
+        self.0.check_attempt_async(attempts_before)
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        self.0.retry_early_returned_errors()
+    }
+
+    fn record_outcome(&mut self, success: bool) {
+        self.0.record_outcome(success)
+    }
+
+    fn peek_next_delay(&self, attempts_before: usize) -> Option<Duration> {
+        self.0.peek_next_delay(attempts_before)
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        self.0.validate()
+    }
+
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::captured_logs;
+
+    crate::retry_strategy!(LoggedTestStrategy, max: 2, |n| Duration::from_secs(n as u64 + 1));
+
+    #[test]
+    fn check_logged() {
+        let logs = captured_logs();
+        let mut strategy = LoggedTestStrategy.logged();
+        assert_eq!(strategy.check_attempt(1).unwrap(), Duration::from_secs(2));
+        assert_eq!(Err(RetryStop::MaxAttempts), strategy.check_attempt(2));
+
+        let records: Vec<_> = logs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|record| record.strip_prefix("DEBUG "))
+            .filter(|record| record.starts_with("LoggedTestStrategy"))
+            .map(String::from)
+            .collect();
+        assert_eq!(
+            vec![
+                "LoggedTestStrategy: attempts_before = 1, retry in 2s",
+                "LoggedTestStrategy: attempts_before = 2, give up - MaxAttempts",
+            ],
+            records
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{RetryStop, RetryStrategy};
+use std::time::Duration;
+
+/// Retry strategy which never retries, so the factory is called exactly once,
+/// e.g. to turn retries off by a feature flag without changing call sites
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::RetryStrategy;
+/// use retry_future::NoRetryStrategy;
+///
+/// assert!(NoRetryStrategy.check_attempt(0).is_err());
+/// ```
+#[derive(Debug, Default, Copy, Clone)]
+pub struct NoRetryStrategy;
+
+impl RetryStrategy for NoRetryStrategy {
+    fn check_attempt(&mut self, _attempts_before: usize) -> Result<Duration, RetryStop> {
+        Err(RetryStop::MaxAttempts)
+    }
+
+    /// `true`, so that every error stops with [StopReason::TooManyAttempts](crate::StopReason::TooManyAttempts)
+    fn retry_early_returned_errors(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &'static str {
+        "no_retry"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RetryFuture, RetryPolicy, StopReason};
+
+    #[tokio::test]
+    async fn single_attempt() {
+        let mut calls = 0;
+        let error = RetryFuture::new(
+            || {
+                calls += 1;
+                async { Err::<(), _>(RetryPolicy::<()>::Retry(None)) }
+            },
+            NoRetryStrategy,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(1, calls);
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        assert_eq!(1, error.len());
+        assert!(matches!(error.last_error(), Some(RetryPolicy::Retry(None))));
+    }
+}
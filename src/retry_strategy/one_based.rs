@@ -0,0 +1,40 @@
+use crate::{AsyncDecision, RetryStop, RetryStrategy, StrategyConfigError};
+use std::time::Duration;
+
+/// Decorator which passes the number of the failed attempt, starting from 1,
+/// instead of `attempts_before` to the inner strategy, e.g. for a strategy ported
+/// from a library which counts attempts this way.
+///
+/// Created by [RetryStrategy::one_based].
+#[derive(Debug, Copy, Clone)]
+pub struct OneBased<S>(pub S);
+
+impl<S: RetryStrategy> RetryStrategy for OneBased<S> {
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        self.0.check_attempt(attempts_before.saturating_add(1))
+    }
+
+    fn check_attempt_async(&mut self, attempts_before: usize) -> Option<AsyncDecision> {
+        self.0.check_attempt_async(attempts_before.saturating_add(1))
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        self.0.retry_early_returned_errors()
+    }
+
+    fn record_outcome(&mut self, success: bool) {
+        self.0.record_outcome(success)
+    }
+
+    fn peek_next_delay(&self, attempts_before: usize) -> Option<Duration> {
+        self.0.peek_next_delay(attempts_before.saturating_add(1))
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        self.0.validate()
+    }
+
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+}
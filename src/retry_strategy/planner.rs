@@ -0,0 +1,84 @@
+use crate::future::FutureFactory;
+use crate::{RetryFuture, RetryStrategy};
+
+/// Configured strategy which creates a fresh instance for every [RetryFuture],
+/// so that one configuration can be reused without sharing the state of the strategy,
+/// e.g. counters of [SlidingWindowStrategy](crate::SlidingWindowStrategy).
+///
+/// Created by [RetryStrategy::into_planner].
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::{LinearRetryStrategy, RetryPolicy, RetryStrategy};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let planner = LinearRetryStrategy::new().max_attempts(3).into_planner();
+/// for id in 0..3 {
+///     let user = planner.retry(move || async move { Ok::<_, RetryPolicy<()>>(id) }).await;
+///     assert_eq!(id, user.unwrap());
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Planner<S> {
+    template: S,
+}
+
+impl<S: RetryStrategy + Clone> Planner<S> {
+    /// Returns a new instance of the strategy as it was configured
+    pub fn strategy(&self) -> S {
+        self.template.clone()
+    }
+
+    /// Creates a [RetryFuture] with a new instance of the strategy
+    pub fn retry<F, Fut, E>(&self, factory: F) -> RetryFuture<F, Fut, S, E>
+    where
+        F: FutureFactory<Future = Fut>,
+    {
+        RetryFuture::new(factory, self.strategy())
+    }
+}
+
+impl<S> From<S> for Planner<S> {
+    fn from(template: S) -> Self {
+        Self { template }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RetryPolicy, SlidingWindowStrategy, StopReason};
+    use std::time::Duration;
+
+    #[tokio::test(start_paused = true)]
+    async fn three_operations() {
+        let planner = SlidingWindowStrategy::new()
+            .max_attempts(2)
+            .window_size(4)
+            .base_delay(Duration::from_secs(1))
+            .into_planner();
+        let tasks = (0..3).map(|_| {
+            let mut attempts = 0;
+            tokio::spawn(planner.retry(move || {
+                attempts += 1;
+                let result =
+                    if attempts < 3 { Err(RetryPolicy::<()>::Retry(None)) } else { Ok(attempts) };
+                async move { result }
+            }))
+        });
+        for task in futures::future::join_all(tasks).await {
+            assert_eq!(3, task.unwrap().unwrap());
+        }
+
+        let error = planner
+            .retry(|| async { Err::<(), _>(RetryPolicy::<()>::Retry(None)) })
+            .await
+            .unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        // outcomes of the operations are recorded by their own instances
+        assert_eq!(0, planner.strategy().failures());
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{RetryStop, RetryStrategy, StrategyConfigError};
+use std::time::Duration;
+
+/// Retry strategy for polling a resource until it is ready, i.e. an "error"
+/// means "not ready yet" and is retried every `interval` up to `max_polls` times.
+///
+/// Unlike [LinearRetryStrategy](crate::LinearRetryStrategy), errors propagated
+/// using `?` are not retried by default, so genuine errors abort polling,
+/// while "not ready" is signalled with [retry!](crate::retry!).
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::RetryStrategy;
+/// use retry_future::PollStrategy;
+/// use std::time::Duration;
+///
+/// let mut strategy = PollStrategy::new().interval(Duration::from_secs(1)).max_polls(2);
+///
+/// assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_secs(1));
+/// assert_eq!(strategy.check_attempt(1).unwrap(), Duration::from_secs(1));
+/// assert!(strategy.check_attempt(2).is_err());
+/// assert!(!strategy.retry_early_returned_errors);
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct PollStrategy {
+    pub interval: Duration,
+    pub max_polls: usize,
+    /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    pub retry_early_returned_errors: bool,
+}
+
+impl Default for PollStrategy {
+    fn default() -> Self {
+        Self { interval: Duration::from_secs(1), max_polls: 10, retry_early_returned_errors: false }
+    }
+}
+
+impl PollStrategy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// [interval](PollStrategy::interval) in milliseconds
+    pub fn interval_ms(self, interval: u64) -> Self {
+        self.interval(Duration::from_millis(interval))
+    }
+
+    /// [interval](PollStrategy::interval) parsed from a string like `"2s"`
+    #[cfg(feature = "humantime")]
+    pub fn interval_str(self, interval: &str) -> Result<Self, humantime::DurationError> {
+        Ok(self.interval(humantime::parse_duration(interval)?))
+    }
+
+    /// [interval](PollStrategy::interval) in seconds
+    pub fn interval_secs(self, interval: u64) -> Self {
+        self.interval(Duration::from_secs(interval))
+    }
+
+    pub fn max_polls(mut self, max_polls: usize) -> Self {
+        self.max_polls = max_polls;
+        self
+    }
+
+    /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    pub fn retry_early_returned_errors(mut self, retry_early_returned_errors: bool) -> Self {
+        self.retry_early_returned_errors = retry_early_returned_errors;
+        self
+    }
+}
+
+impl RetryStrategy for PollStrategy {
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        if attempts_before >= self.max_polls {
+            Err(RetryStop::MaxAttempts)
+        } else {
+            Ok(self.interval)
+        }
+    }
+
+    fn peek_next_delay(&self, attempts_before: usize) -> Option<Duration> {
+        { *self }.check_attempt(attempts_before).ok()
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        self.retry_early_returned_errors
+    }
+
+    fn name(&self) -> &'static str {
+        "poll"
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        if self.max_polls == 0 {
+            return Err(StrategyConfigError::new(self.name(), "max polls must be positive"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{retry, RetryFuture, RetryPolicy, StopReason};
+
+    #[tokio::test(start_paused = true)]
+    async fn check_not_ready_and_abort() {
+        let mut poll = 0;
+        let f = RetryFuture::new(
+            || {
+                poll += 1;
+                let poll = poll;
+                async move {
+                    if poll < 3 {
+                        retry!("not ready");
+                    }
+                    let status: u32 = "failed".parse()?;
+                    Ok::<_, RetryPolicy>(status)
+                }
+            },
+            PollStrategy::new().interval(Duration::from_secs(5)),
+        );
+        let started = tokio::time::Instant::now();
+        let error = f.await.unwrap_err();
+        assert_eq!(StopReason::EarlyReturned, error.reason());
+        assert_eq!(3, error.len());
+        assert_eq!(3, poll);
+        assert_eq!(Duration::from_secs(10), started.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn check_max_polls() {
+        let f = RetryFuture::new(
+            || async { retry!() as Result<(), RetryPolicy> },
+            PollStrategy::new().interval(Duration::from_secs(1)).max_polls(3),
+        );
+        let error = f.await.unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        assert_eq!(4, error.len());
+    }
+}
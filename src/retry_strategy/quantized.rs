@@ -0,0 +1,97 @@
+use crate::{AsyncDecision, RetryStop, RetryStrategy, StrategyConfigError};
+use futures::FutureExt;
+use std::time::Duration;
+
+/// Decorator which rounds every delay of the inner strategy to the nearest multiple
+/// of `quantum`, e.g. for schedulers accepting only delays in 100ms buckets.
+///
+/// Created by [RetryStrategy::quantize].
+#[derive(Debug, Copy, Clone)]
+pub struct Quantized<S> {
+    strategy: S,
+    quantum: Duration,
+}
+
+impl<S> Quantized<S> {
+    pub fn new(strategy: S, quantum: Duration) -> Self {
+        Self { strategy, quantum }
+    }
+
+    fn round(&self, delay: Duration) -> Duration {
+        let quantum = self.quantum.as_nanos();
+        if quantum == 0 {
+            return delay;
+        }
+        let nanos = (delay.as_nanos() + quantum / 2) / quantum * quantum;
+        match u64::try_from(nanos / 1_000_000_000) {
+            Ok(secs) => Duration::new(secs, (nanos % 1_000_000_000) as u32),
+            Err(_) => Duration::MAX,
+        }
+    }
+}
+
+impl<S: RetryStrategy> RetryStrategy for Quantized<S> {
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        self.strategy.check_attempt(attempts_before).map(|delay| self.round(delay))
+    }
+
+    fn check_attempt_async(&mut self, attempts_before: usize) -> Option<AsyncDecision> {
+        let decision = self.strategy.check_attempt_async(attempts_before)?;
+        let quantized = Quantized::new((), self.quantum);
+        Some(decision.map(move |decision| decision.map(|delay| quantized.round(delay))).boxed())
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        self.strategy.retry_early_returned_errors()
+    }
+
+    fn record_outcome(&mut self, success: bool) {
+        self.strategy.record_outcome(success)
+    }
+
+    fn peek_next_delay(&self, attempts_before: usize) -> Option<Duration> {
+        self.strategy.peek_next_delay(attempts_before).map(|delay| self.round(delay))
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        if self.quantum.is_zero() {
+            return Err(StrategyConfigError::new(self.name(), "quantum must be positive"));
+        }
+        self.strategy.validate()
+    }
+
+    fn name(&self) -> &'static str {
+        self.strategy.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExponentialRetryStrategy;
+
+    #[test]
+    fn check_exponential() {
+        let mut strategy = ExponentialRetryStrategy::new()
+            .initial_delay(Duration::from_millis(130))
+            .base(3)
+            .max_attempts(4)
+            .quantize(Duration::from_millis(100));
+        let delays: Vec<_> = (0..).map_while(|n| strategy.check_attempt(n).ok()).collect();
+        // 130ms, 390ms, 1170ms and 3510ms
+        assert_eq!(delays, [100, 400, 1200, 3500].map(Duration::from_millis));
+    }
+
+    #[test]
+    fn check_round() {
+        let quantized = Quantized::new((), Duration::from_millis(100));
+        assert_eq!(Duration::ZERO, quantized.round(Duration::from_millis(49)));
+        assert_eq!(Duration::from_millis(100), quantized.round(Duration::from_millis(50)));
+        assert_eq!(Duration::from_millis(100), quantized.round(Duration::from_millis(100)));
+        assert_eq!(Duration::MAX, quantized.round(Duration::MAX));
+        assert_eq!(
+            Duration::from_millis(7),
+            Quantized::new((), Duration::ZERO).round(Duration::from_millis(7))
+        );
+    }
+}
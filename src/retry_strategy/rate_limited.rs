@@ -0,0 +1,225 @@
+use crate::clock::{Clock, TokioClock};
+use crate::{saturating_mul, RetryStop, RetryStrategy, StrategyConfigError};
+use std::time::{Duration, Instant};
+
+/// Retry strategy which spaces retries so that there are no more than
+/// `permits` of them per `per`, e.g. for APIs with per-window rate limits.
+///
+/// Permits are refilled evenly over time like in a token bucket, which is full
+/// at the beginning. Every [check_attempt](RetryStrategy::check_attempt) takes a permit
+/// and returns the delay until it becomes available. When the strategy is shared between
+/// futures, e.g. by passing `&mut RateLimitedStrategy` to [RetryFuture](crate::RetryFuture),
+/// the rate applies to all of them.
+///
+/// Time is measured by the [clock](RateLimitedStrategy::with_clock), which is
+/// [TokioClock] by default, so it respects paused time as well.
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::RateLimitedStrategy;
+/// use retry_future::RetryStrategy;
+/// use std::time::Duration;
+///
+/// # #[tokio::main(flavor = "current_thread", start_paused = true)]
+/// # async fn main() {
+/// let mut strategy = RateLimitedStrategy::new().permits(2).per(Duration::from_secs(1));
+///
+/// assert_eq!(strategy.check_attempt(0).unwrap(), Duration::ZERO);
+/// assert_eq!(strategy.check_attempt(1).unwrap(), Duration::ZERO);
+/// assert_eq!(strategy.check_attempt(2).unwrap(), Duration::from_millis(500));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateLimitedStrategy<C = TokioClock> {
+    pub permits: u32,
+    pub per: Duration,
+    pub max_attempts: usize,
+    /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    pub retry_early_returned_errors: bool,
+    clock: C,
+    /// When the first permit was taken
+    start: Option<Instant>,
+    /// When the next permit is available if the bucket is empty, relative to `start`
+    theoretical_arrival: Duration,
+}
+
+impl Default for RateLimitedStrategy {
+    fn default() -> Self {
+        Self {
+            permits: 10,
+            per: Duration::from_secs(1),
+            max_attempts: 5,
+            retry_early_returned_errors: true,
+            clock: TokioClock,
+            start: None,
+            theoretical_arrival: Duration::ZERO,
+        }
+    }
+}
+
+impl RateLimitedStrategy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<C> RateLimitedStrategy<C> {
+    /// Replaces the [clock](crate::clock::Clock) measuring time between permits,
+    /// e.g. with the one passed to [RetryFuture::with_clock](crate::RetryFuture::with_clock)
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> RateLimitedStrategy<C2> {
+        RateLimitedStrategy {
+            permits: self.permits,
+            per: self.per,
+            max_attempts: self.max_attempts,
+            retry_early_returned_errors: self.retry_early_returned_errors,
+            clock,
+            start: None,
+            theoretical_arrival: Duration::ZERO,
+        }
+    }
+
+    pub fn permits(mut self, permits: u32) -> Self {
+        self.permits = permits;
+        self
+    }
+
+    pub fn per(mut self, per: Duration) -> Self {
+        self.per = per;
+        self
+    }
+
+    /// [per](RateLimitedStrategy::per) in milliseconds
+    pub fn per_ms(self, per: u64) -> Self {
+        self.per(Duration::from_millis(per))
+    }
+
+    /// [per](RateLimitedStrategy::per) parsed from a string like `"2s"`
+    #[cfg(feature = "humantime")]
+    pub fn per_str(self, per: &str) -> Result<Self, humantime::DurationError> {
+        Ok(self.per(humantime::parse_duration(per)?))
+    }
+
+    /// [per](RateLimitedStrategy::per) in seconds
+    pub fn per_secs(self, per: u64) -> Self {
+        self.per(Duration::from_secs(per))
+    }
+
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    pub fn retry_early_returned_errors(mut self, retry_early_returned_errors: bool) -> Self {
+        self.retry_early_returned_errors = retry_early_returned_errors;
+        self
+    }
+}
+
+impl<C: Clock> RetryStrategy for RateLimitedStrategy<C> {
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        if attempts_before >= self.max_attempts {
+            return Err(RetryStop::MaxAttempts);
+        }
+        let permits = self.permits.max(1);
+        let interval = self.per / permits;
+        // how far ahead of the theoretical arrival time a permit may be taken
+        let burst = saturating_mul(interval, (permits - 1) as u64);
+
+        // durations since `start` saturate instead of overflowing `Instant` with a huge `per`
+        let now = self.clock.now();
+        let now = now.saturating_duration_since(*self.start.get_or_insert(now));
+        let arrival = self.theoretical_arrival.max(now);
+        self.theoretical_arrival = arrival.saturating_add(interval);
+        Ok(arrival.saturating_sub(now.saturating_add(burst)))
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        self.retry_early_returned_errors
+    }
+
+    fn name(&self) -> &'static str {
+        "rate_limited"
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        super::validate_max_attempts(self.name(), self.max_attempts)?;
+        if self.permits == 0 {
+            return Err(StrategyConfigError::new(self.name(), "permits must be positive"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test(start_paused = true)]
+    async fn check_rate() {
+        let mut strategy = RateLimitedStrategy::new()
+            .permits(3)
+            .per(Duration::from_secs(3))
+            .max_attempts(usize::MAX);
+        let delays: Vec<_> = (0..6).map(|n| strategy.check_attempt(n).unwrap()).collect();
+        assert_eq!(
+            delays,
+            [0, 0, 0, 1, 2, 3].map(Duration::from_secs),
+            "the burst is spent, then a permit per second"
+        );
+
+        // the last retry is scheduled 1s ahead, which still fits in the burst
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_secs(0));
+        tokio::time::advance(Duration::from_secs(10)).await;
+        // the bucket holds no more than 3 permits
+        let delays: Vec<_> = (0..4).map(|n| strategy.check_attempt(n).unwrap()).collect();
+        assert_eq!(delays, [0, 0, 0, 1].map(Duration::from_secs));
+    }
+
+    #[derive(Debug, Clone)]
+    struct ManualClock(Arc<Mutex<Instant>>);
+
+    impl Clock for ManualClock {
+        type Sleep = futures::future::Ready<()>;
+
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+
+        fn sleep(&self, _duration: Duration) -> Self::Sleep {
+            futures::future::ready(())
+        }
+    }
+
+    #[test]
+    fn check_clock() {
+        let now = Arc::new(Mutex::new(Instant::now()));
+        let mut strategy = RateLimitedStrategy::new()
+            .permits(1)
+            .per(Duration::from_secs(2))
+            .with_clock(ManualClock(now.clone()));
+        assert_eq!(strategy.check_attempt(0).unwrap(), Duration::ZERO);
+        assert_eq!(strategy.check_attempt(1).unwrap(), Duration::from_secs(2));
+        *now.lock().unwrap() += Duration::from_secs(3);
+        assert_eq!(strategy.check_attempt(2).unwrap(), Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn check_overflow() {
+        let mut strategy = RateLimitedStrategy::new().permits(2).per(Duration::MAX);
+        assert_eq!(strategy.check_attempt(0).unwrap(), Duration::ZERO);
+        assert_eq!(strategy.check_attempt(1).unwrap(), Duration::ZERO);
+        assert_eq!(strategy.check_attempt(2).unwrap(), Duration::MAX / 2);
+        assert!(strategy.check_attempt(3).unwrap() >= Duration::MAX / 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn check_max_attempts() {
+        let mut strategy = RateLimitedStrategy::new().max_attempts(2);
+        assert!(strategy.check_attempt(1).is_ok());
+        assert_eq!(Err(RetryStop::MaxAttempts), strategy.check_attempt(2));
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{RetryStop, RetryStrategy, StrategyConfigError};
+use std::time::Duration;
+
+/// Retry strategy cycling through a fixed list of delays,
+/// e.g. for tests or schedules which do not follow a formula
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::RetryStrategy;
+/// use retry_future::RoundRobinStrategy;
+/// use std::time::Duration;
+///
+/// let mut strategy =
+///     RoundRobinStrategy::new(vec![Duration::from_secs(1), Duration::from_secs(5)]).max_attempts(3);
+///
+/// assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_secs(1));
+/// assert_eq!(strategy.check_attempt(1).unwrap(), Duration::from_secs(5));
+/// assert_eq!(strategy.check_attempt(2).unwrap(), Duration::from_secs(1));
+///
+/// assert!(strategy.check_attempt(3).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct RoundRobinStrategy {
+    /// Delay before retry `n` is `delays[n % delays.len()]`. If it is empty, nothing is retried
+    pub delays: Vec<Duration>,
+    /// Retry indefinitely if `None`
+    pub max_attempts: Option<usize>,
+    /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    pub retry_early_returned_errors: bool,
+}
+
+impl RoundRobinStrategy {
+    /// # Panics
+    ///
+    /// Panics if `delays` is empty.
+    pub fn new(delays: Vec<Duration>) -> Self {
+        assert!(!delays.is_empty(), "delays of RoundRobinStrategy must not be empty");
+        Self { delays, max_attempts: None, retry_early_returned_errors: true }
+    }
+
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    pub fn retry_early_returned_errors(mut self, retry_early_returned_errors: bool) -> Self {
+        self.retry_early_returned_errors = retry_early_returned_errors;
+        self
+    }
+}
+
+impl RetryStrategy for RoundRobinStrategy {
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        self.peek_next_delay(attempts_before).ok_or(RetryStop::MaxAttempts)
+    }
+
+    fn peek_next_delay(&self, attempts_before: usize) -> Option<Duration> {
+        if self.delays.is_empty() || self.max_attempts.is_some_and(|max| attempts_before >= max) {
+            return None;
+        }
+        Some(self.delays[attempts_before % self.delays.len()])
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        self.retry_early_returned_errors
+    }
+
+    fn name(&self) -> &'static str {
+        "round_robin"
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        if self.delays.is_empty() {
+            return Err(StrategyConfigError::new(self.name(), "delays must not be empty"));
+        }
+        if self.max_attempts == Some(0) {
+            return Err(StrategyConfigError::new(self.name(), "max attempts must be positive"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_cycle() {
+        let mut strategy = RoundRobinStrategy::new([1, 2, 3].map(Duration::from_secs).to_vec());
+        let delays: Vec<_> = (0..7).map(|n| strategy.check_attempt(n).unwrap()).collect();
+        assert_eq!(delays, [1, 2, 3, 1, 2, 3, 1].map(Duration::from_secs));
+
+        let mut strategy = strategy.max_attempts(7);
+        assert_eq!(Err(RetryStop::MaxAttempts), strategy.check_attempt(7));
+    }
+
+    #[test]
+    fn check_empty() {
+        let mut strategy = RoundRobinStrategy::new(vec![Duration::from_secs(1)]);
+        strategy.delays.clear();
+        assert_eq!(Err(RetryStop::MaxAttempts), strategy.check_attempt(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "must not be empty")]
+    fn check_new_empty() {
+        RoundRobinStrategy::new(vec![]);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{AsyncDecision, RetryStop, RetryStrategy, StrategyConfigError};
+use futures::FutureExt;
+use std::time::Duration;
+
+/// Decorator which rounds every delay of the inner strategy to whole milliseconds,
+/// e.g. when delays are passed to systems which truncate them.
+///
+/// Created by [RetryStrategy::round_to_millis].
+#[derive(Debug, Copy, Clone)]
+pub struct RoundToMillis<S>(pub S);
+
+fn round(delay: Duration) -> Duration {
+    let millis = (delay.as_nanos() + 500_000) / 1_000_000;
+    Duration::from_millis(u64::try_from(millis).unwrap_or(u64::MAX))
+}
+
+impl<S: RetryStrategy> RetryStrategy for RoundToMillis<S> {
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        self.0.check_attempt(attempts_before).map(round)
+    }
+
+    fn check_attempt_async(&mut self, attempts_before: usize) -> Option<AsyncDecision> {
+        let decision = self.0.check_attempt_async(attempts_before)?;
+        Some(decision.map(|decision| decision.map(round)).boxed())
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        self.0.retry_early_returned_errors()
+    }
+
+    fn record_outcome(&mut self, success: bool) {
+        self.0.record_outcome(success)
+    }
+
+    fn peek_next_delay(&self, attempts_before: usize) -> Option<Duration> {
+        self.0.peek_next_delay(attempts_before).map(round)
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        self.0.validate()
+    }
+
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InfiniteRetryStrategy, Jitter};
+
+    #[test]
+    fn check_jitter() {
+        let mut strategy = InfiniteRetryStrategy::new()
+            .duration_between_retries(Duration::from_secs(1))
+            .jitter(Jitter::Full)
+            .round_to_millis();
+        for n in 0..100 {
+            let delay = strategy.check_attempt(n).unwrap();
+            assert_eq!(0, delay.subsec_nanos() % 1_000_000, "{delay:?}");
+            assert!(delay <= Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn check_round() {
+        assert_eq!(Duration::from_millis(2), round(Duration::from_micros(1500)));
+        assert_eq!(Duration::from_millis(1), round(Duration::from_micros(1499)));
+        assert_eq!(Duration::ZERO, round(Duration::from_nanos(1)));
+        assert_eq!(Duration::from_millis(u64::MAX), round(Duration::MAX));
+    }
+}
@@ -0,0 +1,114 @@
+use super::lock;
+use crate::{AsyncDecision, RetryStop, RetryStrategy, StrategyConfigError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Strategy which can be cloned and shared between futures running concurrently
+/// on several threads, e.g. to limit retries of all requests to one service.
+///
+/// Unlike `Arc<Mutex<T>>`, the type of the inner strategy is erased,
+/// so differently configured strategies can be shared through one type.
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::{LinearRetryStrategy, SharedStrategy};
+///
+/// let strategy = SharedStrategy::new(LinearRetryStrategy::new().max_attempts(10));
+/// let for_another_task = strategy.clone();
+/// ```
+#[derive(Clone)]
+pub struct SharedStrategy(Arc<Mutex<dyn RetryStrategy + Send>>);
+
+impl SharedStrategy {
+    pub fn new(strategy: impl RetryStrategy + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(strategy)))
+    }
+}
+
+impl std::fmt::Debug for SharedStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SharedStrategy").field(&self.name()).finish()
+    }
+}
+
+impl RetryStrategy for SharedStrategy {
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        lock(&self.0).check_attempt(attempts_before)
+    }
+
+    fn check_attempt_async(&mut self, attempts_before: usize) -> Option<AsyncDecision> {
+        lock(&self.0).check_attempt_async(attempts_before)
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        lock(&self.0).retry_early_returned_errors()
+    }
+
+    fn record_outcome(&mut self, success: bool) {
+        lock(&self.0).record_outcome(success)
+    }
+
+    fn peek_next_delay(&self, attempts_before: usize) -> Option<Duration> {
+        lock(&self.0).peek_next_delay(attempts_before)
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        lock(&self.0).validate()
+    }
+
+    fn name(&self) -> &'static str {
+        lock(&self.0).name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RetryFuture, RetryPolicy, StopReason};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Allows `max` retries in total, no matter how many futures share it
+    struct TotalRetries {
+        retries: Arc<AtomicUsize>,
+        max: usize,
+    }
+
+    impl RetryStrategy for TotalRetries {
+        fn check_attempt(&mut self, _attempts_before: usize) -> Result<Duration, RetryStop> {
+            if self.retries.load(Ordering::SeqCst) == self.max {
+                return Err(RetryStop::Budget);
+            }
+            self.retries.fetch_add(1, Ordering::SeqCst);
+            Ok(Duration::from_millis(10))
+        }
+
+        fn retry_early_returned_errors(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shared_between_tasks() {
+        let retries = Arc::new(AtomicUsize::new(0));
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let strategy = SharedStrategy::new(TotalRetries { retries: retries.clone(), max: 5 });
+        let tasks = (0..2).map(|_| {
+            let attempts = attempts.clone();
+            let strategy = strategy.clone();
+            tokio::spawn(RetryFuture::new(
+                move || {
+                    attempts.fetch_add(1, Ordering::SeqCst);
+                    async { Err::<(), _>(RetryPolicy::<()>::Retry(None)) }
+                },
+                strategy,
+            ))
+        });
+        for task in futures::future::join_all(tasks).await {
+            assert_eq!(StopReason::Budget, task.unwrap().unwrap_err().reason());
+        }
+        assert_eq!(5, retries.load(Ordering::SeqCst));
+        // the first attempt of every task is not a retry
+        assert_eq!(7, attempts.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,211 @@
+use crate::{saturating_mul, RetryStop, RetryStrategy, StrategyConfigError};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Retry strategy which escalates delay based on failures among the last
+/// `window_size` [outcomes](crate::retry_strategy::RetryStrategy::record_outcome)
+/// instead of `attempts_before`.
+///
+/// Successes do not reset the escalation, so an operation which keeps flapping
+/// (succeed, fail, succeed, fail...) is backed off as well. The strategy is meant to be
+/// shared between futures, e.g. by passing `&mut SlidingWindowStrategy` to
+/// [RetryFuture](crate::RetryFuture).
+///
+/// While the failure rate in the window is below `failure_rate_threshold`,
+/// `base_delay` is used. Otherwise the delay is `base_delay * 2^failures`.
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::RetryStrategy;
+/// use retry_future::SlidingWindowStrategy;
+/// use std::time::Duration;
+///
+/// let mut strategy = SlidingWindowStrategy::new()
+///     .window_size(4)
+///     .failure_rate_threshold(0.5)
+///     .base_delay(Duration::from_secs(1));
+///
+/// strategy.record_outcome(false);
+/// strategy.record_outcome(true);
+/// strategy.record_outcome(false);
+/// assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_secs(4));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SlidingWindowStrategy {
+    pub window_size: usize,
+    /// Share of failures in the window, from `0.0` to `1.0`, starting from which delay escalates
+    pub failure_rate_threshold: f64,
+    pub base_delay: Duration,
+    pub max_attempts: usize,
+    /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    pub retry_early_returned_errors: bool,
+    outcomes: VecDeque<bool>,
+}
+
+impl Default for SlidingWindowStrategy {
+    fn default() -> Self {
+        Self {
+            window_size: 10,
+            failure_rate_threshold: 0.5,
+            base_delay: Duration::from_millis(500),
+            max_attempts: 5,
+            retry_early_returned_errors: true,
+            outcomes: VecDeque::new(),
+        }
+    }
+}
+
+impl SlidingWindowStrategy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn window_size(mut self, window_size: usize) -> Self {
+        self.window_size = window_size;
+        self
+    }
+
+    pub fn failure_rate_threshold(mut self, failure_rate_threshold: f64) -> Self {
+        self.failure_rate_threshold = failure_rate_threshold;
+        self
+    }
+
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// [base_delay](SlidingWindowStrategy::base_delay) in milliseconds
+    pub fn base_delay_ms(self, base_delay: u64) -> Self {
+        self.base_delay(Duration::from_millis(base_delay))
+    }
+
+    /// [base_delay](SlidingWindowStrategy::base_delay) parsed from a string like `"2s"`
+    #[cfg(feature = "humantime")]
+    pub fn base_delay_str(self, base_delay: &str) -> Result<Self, humantime::DurationError> {
+        Ok(self.base_delay(humantime::parse_duration(base_delay)?))
+    }
+
+    /// [base_delay](SlidingWindowStrategy::base_delay) in seconds
+    pub fn base_delay_secs(self, base_delay: u64) -> Self {
+        self.base_delay(Duration::from_secs(base_delay))
+    }
+
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// See [RetryStrategy::retry_early_returned_errors](crate::retry_strategy::RetryStrategy::retry_early_returned_errors)
+    pub fn retry_early_returned_errors(mut self, retry_early_returned_errors: bool) -> Self {
+        self.retry_early_returned_errors = retry_early_returned_errors;
+        self
+    }
+
+    /// Number of failures among the last `window_size` outcomes
+    pub fn failures(&self) -> usize {
+        self.outcomes.iter().filter(|success| !**success).count()
+    }
+}
+
+impl RetryStrategy for SlidingWindowStrategy {
+    fn check_attempt(&mut self, attempts_before: usize) -> Result<Duration, RetryStop> {
+        if attempts_before >= self.max_attempts {
+            return Err(RetryStop::MaxAttempts);
+        }
+        let failures = self.failures();
+        if self.outcomes.is_empty()
+            || (failures as f64 / self.outcomes.len() as f64) < self.failure_rate_threshold
+        {
+            return Ok(self.base_delay);
+        }
+        let exponent = u32::try_from(failures).unwrap_or(u32::MAX);
+        Ok(saturating_mul(self.base_delay, 2u64.saturating_pow(exponent)))
+    }
+
+    fn retry_early_returned_errors(&self) -> bool {
+        self.retry_early_returned_errors
+    }
+
+    fn name(&self) -> &'static str {
+        "sliding_window"
+    }
+
+    fn validate(&self) -> Result<(), StrategyConfigError> {
+        super::validate_max_attempts(self.name(), self.max_attempts)?;
+        if self.window_size == 0 {
+            return Err(StrategyConfigError::new(self.name(), "window size must be positive"));
+        }
+        if !(0.0..=1.0).contains(&self.failure_rate_threshold) {
+            return Err(StrategyConfigError::new(
+                self.name(),
+                format!(
+                    "failure rate threshold {} must be from 0 to 1",
+                    self.failure_rate_threshold
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn record_outcome(&mut self, success: bool) {
+        self.outcomes.push_back(success);
+        while self.outcomes.len() > self.window_size {
+            self.outcomes.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RetryFuture, RetryPolicy};
+
+    #[test]
+    fn check_flapping() {
+        let mut strategy = SlidingWindowStrategy::new()
+            .window_size(4)
+            .failure_rate_threshold(0.5)
+            .base_delay(Duration::from_secs(1));
+        assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_secs(1));
+
+        strategy.record_outcome(true);
+        strategy.record_outcome(false);
+        // 1 failure out of 2
+        assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_secs(2));
+
+        strategy.record_outcome(true);
+        strategy.record_outcome(false);
+        // 2 failures out of 4
+        assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_secs(4));
+
+        strategy.record_outcome(true);
+        strategy.record_outcome(true);
+        strategy.record_outcome(true);
+        // only 1 failure is left in the window
+        assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn check_flapping_future() {
+        let mut strategy =
+            SlidingWindowStrategy::new().window_size(6).base_delay(Duration::from_millis(1));
+        for _ in 0..3 {
+            let mut attempt = 0;
+            RetryFuture::new(
+                || {
+                    attempt += 1;
+                    let result =
+                        if attempt == 1 { Err(RetryPolicy::<()>::Retry(None)) } else { Ok(()) };
+                    async move { result }
+                },
+                &mut strategy,
+            )
+            .await
+            .unwrap();
+        }
+        assert_eq!(3, strategy.failures());
+        assert_eq!(strategy.check_attempt(0).unwrap(), Duration::from_millis(8));
+    }
+}
@@ -0,0 +1,226 @@
+use crate::clock::{Clock, TokioClock};
+use crate::error::{DelayHint, RetryError, StopReason};
+use crate::retry_strategy::{AsyncDecision, RetryStrategy};
+use crate::RetryPolicy;
+use futures::{ready, Stream};
+use pin_project::pin_project;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Retries errors of a long-lived stream in place, without recreating it
+pub trait RetryStreamExt<T, E>: Stream<Item = Result<T, RetryPolicy<E>>> + Sized {
+    /// On [RetryPolicy::Retry] waits for the delay of `retry_strategy` and polls
+    /// the stream again, so only [Fail](RetryPolicy::Fail) and exhaustion of the strategy
+    /// are yielded as [RetryError]. Errors are counted from the last yielded item.
+    ///
+    /// The stream goes on after an error, it is up to the caller to stop polling it.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use futures::StreamExt;
+    /// use retry_future::{LinearRetryStrategy, RetryPolicy, RetryStreamExt};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let items = futures::stream::iter([Ok(1), Err(RetryPolicy::<()>::Retry(None)), Ok(2)]);
+    /// let strategy = LinearRetryStrategy::new().delay_between_retries(Duration::from_millis(1));
+    /// let items: Vec<_> = items.retry_items(strategy).map(Result::unwrap).collect().await;
+    /// assert_eq!(vec![1, 2], items);
+    /// # }
+    /// ```
+    fn retry_items<RS: RetryStrategy>(self, retry_strategy: RS) -> RetryItems<Self, RS, E> {
+        RetryItems {
+            stream: self,
+            retry_strategy,
+            attempts_before: 0,
+            errors: Vec::new(),
+            deciding: None,
+            delay: None,
+        }
+    }
+}
+
+impl<T, E, S: Stream<Item = Result<T, RetryPolicy<E>>>> RetryStreamExt<T, E> for S {}
+
+/// Stream returned by [retry_items](RetryStreamExt::retry_items)
+#[pin_project]
+pub struct RetryItems<S, RS, E> {
+    #[pin]
+    stream: S,
+    retry_strategy: RS,
+    attempts_before: usize,
+    errors: Vec<RetryPolicy<E>>,
+    /// Decision of [check_attempt_async](RetryStrategy::check_attempt_async) to await
+    deciding: Option<(AsyncDecision, DelayHint)>,
+    #[pin]
+    delay: Option<<TokioClock as Clock>::Sleep>,
+}
+
+impl<T, E, S, RS> Stream for RetryItems<S, RS, E>
+where
+    S: Stream<Item = Result<T, RetryPolicy<E>>>,
+    RS: RetryStrategy,
+{
+    type Item = Result<T, RetryError<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if let Some(delay) = this.delay.as_mut().as_pin_mut() {
+                ready!(delay.poll(cx));
+                this.delay.set(None);
+            }
+            let decision = match this.deciding {
+                Some((decision, hint)) => {
+                    let decision = ready!(decision.as_mut().poll(cx));
+                    let hint = *hint;
+                    *this.deciding = None;
+                    decision.map(|delay| hint.apply(delay)).map_err(StopReason::from)
+                }
+                None => {
+                    let err = match ready!(this.stream.as_mut().poll_next(cx)) {
+                        None => return Poll::Ready(None),
+                        Some(Ok(item)) => {
+                            this.retry_strategy.record_outcome(true);
+                            *this.attempts_before = 0;
+                            this.errors.clear();
+                            return Poll::Ready(Some(Ok(item)));
+                        }
+                        Some(Err(err)) => err,
+                    };
+                    this.retry_strategy.record_outcome(false);
+                    let decision = match &err {
+                        RetryPolicy::Retry(Some(e))
+                            if e.is_early_returned
+                                && !this.retry_strategy.retry_early_returned_errors() =>
+                        {
+                            Err(StopReason::EarlyReturned)
+                        }
+                        RetryPolicy::Retry(maybe_err) => {
+                            let hint = DelayHint::of(maybe_err.as_ref());
+                            let attempts_before = *this.attempts_before;
+                            match this.retry_strategy.check_attempt_async(attempts_before) {
+                                Some(decision) => {
+                                    *this.deciding = Some((decision, hint));
+                                    this.errors.push(err);
+                                    continue;
+                                }
+                                None => this
+                                    .retry_strategy
+                                    .check_attempt(attempts_before)
+                                    .map(|delay| hint.apply(delay))
+                                    .map_err(StopReason::from),
+                            }
+                        }
+                        RetryPolicy::Fail(_) => Err(StopReason::Fail),
+                    };
+                    this.errors.push(err);
+                    decision
+                }
+            };
+            match decision {
+                Ok(delay) => {
+                    *this.attempts_before += 1;
+                    this.delay.set(Some(TokioClock.sleep(delay)));
+                }
+                Err(reason) => {
+                    *this.attempts_before = 0;
+                    let errors = std::mem::take(this.errors);
+                    return Poll::Ready(Some(Err(RetryError::new(errors, reason))));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AsyncRetryStrategy, AsyncStrategy, LinearRetryStrategy, RetryStop};
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    #[tokio::test(start_paused = true)]
+    async fn retry_transient_errors() {
+        let items = futures::stream::iter([
+            Ok(1),
+            Err(RetryPolicy::Retry(None)),
+            Err(RetryPolicy::Retry(None)),
+            Ok(2),
+            Err(RetryPolicy::Retry(None)),
+            Ok(3),
+            Err(RetryPolicy::Fail("fatal")),
+            Ok(4),
+        ]);
+        let strategy = LinearRetryStrategy::new()
+            .max_attempts(2)
+            .delay_between_retries(Duration::from_secs(1));
+        let started = tokio::time::Instant::now();
+        let items: Vec<_> = items.retry_items(strategy).collect().await;
+        assert_eq!(5, items.len());
+        assert_eq!(
+            vec![1, 2, 3],
+            items[..3].iter().map(|item| *item.as_ref().unwrap()).collect::<Vec<_>>()
+        );
+        let error = items[3].as_ref().unwrap_err();
+        assert_eq!(StopReason::Fail, error.reason());
+        assert_eq!(1, error.len());
+        assert_eq!(4, *items[4].as_ref().unwrap());
+        assert_eq!(Duration::from_secs(3), started.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn too_many_attempts() {
+        let items = futures::stream::iter([
+            Err(RetryPolicy::<()>::Retry(None)),
+            Err(RetryPolicy::Retry(None)),
+            Err(RetryPolicy::Retry(None)),
+            Ok(1),
+        ]);
+        let strategy = LinearRetryStrategy::new().max_attempts(2);
+        let mut items = std::pin::pin!(items.retry_items(strategy));
+        let error = items.next().await.unwrap().unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        assert_eq!(3, error.len());
+        assert_eq!(1, items.next().await.unwrap().unwrap());
+        assert!(items.next().await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn async_strategy() {
+        struct AfterSleep;
+
+        impl AsyncRetryStrategy for AfterSleep {
+            fn check_attempt(&mut self, attempts_before: usize) -> AsyncDecision {
+                Box::pin(async move {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    match attempts_before {
+                        0 => Ok(Duration::from_secs(2)),
+                        _ => Err(RetryStop::MaxAttempts),
+                    }
+                })
+            }
+
+            fn retry_early_returned_errors(&self) -> bool {
+                true
+            }
+        }
+
+        let items = futures::stream::iter([
+            Err(RetryPolicy::<()>::Retry(None)),
+            Ok(1),
+            Err(RetryPolicy::Retry(None)),
+            Err(RetryPolicy::Retry(None)),
+        ]);
+        let started = tokio::time::Instant::now();
+        let items: Vec<_> = items.retry_items(AsyncStrategy(AfterSleep)).collect().await;
+        assert_eq!(1, *items[0].as_ref().unwrap());
+        let error = items[1].as_ref().unwrap_err();
+        assert_eq!(StopReason::TooManyAttempts, error.reason());
+        assert_eq!(2, error.len());
+        assert_eq!(Duration::from_secs(1 + 2 + 1 + 2 + 1), started.elapsed());
+    }
+}
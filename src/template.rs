@@ -0,0 +1,85 @@
+use crate::future::{FutureFactory, RetryFuture};
+
+/// Configuration of [RetryFuture] which can be built many times,
+/// e.g. to launch several identical operations.
+///
+/// Unlike futures, the factory and the [retry strategy](crate::retry_strategy::RetryStrategy)
+/// are usually `Clone`, so every [build](RetryFutureTemplate::build) gets
+/// its own copies of them.
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::{LinearRetryStrategy, RetryFutureTemplate, RetryPolicy};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let template = RetryFutureTemplate::new(
+///     || async { Ok::<_, RetryPolicy>(42) },
+///     LinearRetryStrategy::new(),
+/// );
+///
+/// let (a, b) = futures::join!(template.build(), template.build());
+/// assert_eq!(42, a.unwrap());
+/// assert_eq!(42, b.unwrap());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryFutureTemplate<F, RS> {
+    factory: F,
+    retry_strategy: RS,
+}
+
+impl<F, RS> RetryFutureTemplate<F, RS>
+where
+    F: FutureFactory + Clone,
+    RS: Clone,
+{
+    pub fn new(factory: F, retry_strategy: RS) -> Self {
+        Self { factory, retry_strategy }
+    }
+
+    /// Creates a new [RetryFuture] with clones of the factory and the strategy
+    pub fn build<E>(&self) -> RetryFuture<F, F::Future, RS, E> {
+        RetryFuture::new(self.factory.clone(), self.retry_strategy.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LinearRetryStrategy, RetryPolicy};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test(start_paused = true)]
+    async fn build_concurrently() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let template = RetryFutureTemplate::new(
+            {
+                let attempts = attempts.clone();
+                move || {
+                    let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        // every future fails on its first attempt
+                        if attempt < 3 {
+                            Err(RetryPolicy::<()>::Retry(None))
+                        } else {
+                            Ok(attempt)
+                        }
+                    }
+                }
+            },
+            LinearRetryStrategy::new().delay_between_retries(Duration::from_secs(1)),
+        );
+
+        let started = tokio::time::Instant::now();
+        let results = futures::future::join_all((0..3).map(|_| template.build())).await;
+        let mut results: Vec<_> = results.into_iter().map(Result::unwrap).collect();
+        results.sort();
+        assert_eq!(vec![3, 4, 5], results);
+        assert_eq!(6, attempts.load(Ordering::SeqCst));
+        assert_eq!(Duration::from_secs(1), started.elapsed());
+    }
+}
@@ -0,0 +1,220 @@
+//! Helpers for testing [retry strategies](crate::RetryStrategy)
+
+use crate::RetryStrategy;
+use std::future::Future;
+use std::time::Duration;
+
+/// Calls [check_attempt](crate::RetryStrategy::check_attempt) with increasing `attempts_before`
+/// until it returns an error and asserts that collected delays are equal to `expected`.
+///
+/// At most `expected.len() + 1` attempts are checked, so that strategies which never
+/// give up fail the assertion instead of looping forever.
+///
+/// # Panics
+///
+/// Panics if the strategy decides [asynchronously](crate::RetryStrategy::check_attempt_async),
+/// since its delays cannot be collected without awaiting them.
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::test_util::assert_delays;
+/// use retry_future::LinearRetryStrategy;
+/// use std::time::Duration;
+///
+/// let strategy = LinearRetryStrategy::new()
+///     .max_attempts(2)
+///     .delay_between_retries(Duration::from_secs(1));
+/// assert_delays(strategy, &[Duration::from_secs(1), Duration::from_secs(1)]);
+/// ```
+#[track_caller]
+pub fn assert_delays<RS: RetryStrategy>(mut strategy: RS, expected: &[Duration]) {
+    let mut delays = Vec::with_capacity(expected.len());
+    for attempts_before in 0..=expected.len() {
+        assert!(
+            strategy.check_attempt_async(attempts_before).is_none(),
+            "assert_delays does not support asynchronous retry strategies"
+        );
+        match strategy.check_attempt(attempts_before) {
+            Ok(delay) => delays.push(delay),
+            Err(_) => break,
+        }
+    }
+    assert_eq!(expected, delays.as_slice(), "unexpected delays of the retry strategy");
+}
+
+/// Runs `future` on a new single-threaded tokio runtime whose clock is paused,
+/// so sleeps between attempts complete instantly, and returns its output with
+/// the durations the clock was advanced by, e.g. the delays between attempts.
+///
+/// The clock is advanced automatically whenever all tasks wait for timers.
+///
+/// # Panics
+///
+/// Panics if it is called inside of an asynchronous context.
+///
+/// ## Examples
+///
+/// ```rust
+/// use retry_future::test_util::run_with_mock_clock;
+/// use retry_future::{LinearRetryStrategy, RetryFuture, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let (result, advanced) = run_with_mock_clock(RetryFuture::new(
+///     || async { Err::<(), _>(RetryPolicy::<()>::Retry(None)) },
+///     LinearRetryStrategy::new().max_attempts(2).delay_between_retries(Duration::from_secs(5)),
+/// ));
+/// assert!(result.is_err());
+/// assert_eq!(vec![Duration::from_secs(5); 2], advanced);
+/// ```
+pub fn run_with_mock_clock<F: Future>(future: F) -> (F::Output, Vec<Duration>) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .start_paused(true)
+        .build()
+        .expect("failed to build tokio runtime");
+    runtime.block_on(async {
+        let mut future = std::pin::pin!(future);
+        let mut advanced = Vec::new();
+        let mut last_poll = tokio::time::Instant::now();
+        let output = std::future::poll_fn(|cx| {
+            let now = tokio::time::Instant::now();
+            if now > last_poll {
+                advanced.push(now - last_poll);
+                last_poll = now;
+            }
+            future.as_mut().poll(cx)
+        })
+        .await;
+        (output, advanced)
+    })
+}
+
+/// Installs a logger capturing records as `LEVEL message` and returns them
+#[cfg(all(test, feature = "log"))]
+pub(crate) fn captured_logs() -> &'static std::sync::Mutex<Vec<String>> {
+    use std::sync::{Mutex, Once};
+
+    static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    static INSTALL: Once = Once::new();
+
+    struct Capturer;
+
+    impl log::Log for Capturer {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            let line = format!("{} {}", record.level(), record.args());
+            RECORDS.lock().unwrap().push(line);
+        }
+
+        fn flush(&self) {}
+    }
+
+    INSTALL.call_once(|| {
+        log::set_logger(&Capturer).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+    });
+    &RECORDS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ExponentialRetryStrategy, InfiniteRetryStrategy, LinearRetryStrategy, RetryFuture,
+        RetryPolicy, StopReason,
+    };
+
+    #[test]
+    fn exponential() {
+        let strategy =
+            ExponentialRetryStrategy::new().max_attempts(4).initial_delay(Duration::from_secs(1));
+        assert_delays(
+            strategy,
+            &[
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+                Duration::from_secs(4),
+                Duration::from_secs(8),
+            ],
+        );
+    }
+
+    #[test]
+    fn exponential_by_ref() {
+        let mut strategy =
+            ExponentialRetryStrategy::new().max_attempts(1).initial_delay(Duration::from_secs(1));
+        assert_delays(&mut strategy, &[Duration::from_secs(1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected delays")]
+    fn exponential_mismatch() {
+        let strategy =
+            ExponentialRetryStrategy::new().max_attempts(2).initial_delay(Duration::from_secs(1));
+        assert_delays(strategy, &[Duration::from_secs(1), Duration::from_secs(3)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected delays")]
+    fn exponential_more_attempts() {
+        let strategy =
+            ExponentialRetryStrategy::new().max_attempts(3).initial_delay(Duration::from_secs(1));
+        assert_delays(strategy, &[Duration::from_secs(1), Duration::from_secs(2)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected delays")]
+    fn infinite() {
+        assert_delays(InfiniteRetryStrategy::new(), &[Duration::from_secs(1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not support asynchronous")]
+    fn asynchronous() {
+        struct Never;
+
+        impl crate::AsyncRetryStrategy for Never {
+            fn check_attempt(&mut self, _attempts_before: usize) -> crate::AsyncDecision {
+                Box::pin(futures::future::pending())
+            }
+
+            fn retry_early_returned_errors(&self) -> bool {
+                true
+            }
+        }
+
+        assert_delays(crate::AsyncStrategy(Never), &[]);
+    }
+
+    #[test]
+    fn mock_clock_exponential() {
+        let mut attempt = 0;
+        let (result, advanced) = run_with_mock_clock(RetryFuture::new(
+            move || {
+                attempt += 1;
+                let result =
+                    if attempt < 4 { Err(RetryPolicy::<()>::Retry(None)) } else { Ok(attempt) };
+                async move { result }
+            },
+            ExponentialRetryStrategy::new().max_attempts(5).initial_delay(Duration::from_secs(1)),
+        ));
+        assert_eq!(4, result.unwrap());
+        assert_eq!(vec![1, 2, 4], advanced.iter().map(Duration::as_secs).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn mock_clock_gives_up() {
+        let (result, advanced) = run_with_mock_clock(RetryFuture::new(
+            || async { Err::<(), _>(RetryPolicy::<()>::Retry(None)) },
+            LinearRetryStrategy::new()
+                .max_attempts(3)
+                .delay_between_retries(Duration::from_secs(60)),
+        ));
+        assert_eq!(StopReason::TooManyAttempts, result.unwrap_err().reason());
+        assert_eq!(vec![Duration::from_secs(60); 3], advanced);
+    }
+}
@@ -0,0 +1,31 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Failed attempt of [RetryFuture](crate::RetryFuture), see
+/// [record_timeline](crate::RetryFuture::record_timeline)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttemptRecord {
+    /// Number of failed attempts before this one
+    pub attempt: usize,
+    /// Delay before the next attempt or `None` if retrying stopped
+    pub delay: Option<Duration>,
+    /// Description of the error without backtrace
+    pub error: String,
+}
+
+/// Shared handle to [attempt records](AttemptRecord) of a [RetryFuture](crate::RetryFuture)
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    records: Arc<Mutex<Vec<AttemptRecord>>>,
+}
+
+impl Timeline {
+    /// Returns records collected so far
+    pub fn records(&self) -> Vec<AttemptRecord> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub(crate) fn push(&self, record: AttemptRecord) {
+        self.records.lock().unwrap_or_else(|e| e.into_inner()).push(record);
+    }
+}